@@ -0,0 +1,68 @@
+//! Renders a single frame without opening a window and saves it as a PNG.
+//! Run with `cargo run --release --example headless -- frame.png [width] [height] [--serial] [--palette]`
+
+use raycast_rust::{map, palette, player, renderer, settings, sky, sprite, texture, input_handler};
+
+fn main() {
+    let serial = std::env::args().any(|arg| arg == "--serial");
+    let indexed = std::env::args().any(|arg| arg == "--palette");
+    let args: Vec<String> = std::env::args().filter(|arg| arg != "--serial" && arg != "--palette").collect();
+    let path = args.get(1).cloned().unwrap_or_else(|| "frame.png".to_string());
+
+    let mut renderer = renderer::Renderer::new(
+        vec![
+            texture::Texture::load("assets/brick2.jpg"),
+            texture::Texture::load("assets/wood.jpg"),
+            texture::Texture::load("assets/metal.jpg")
+        ],
+        vec![texture::Texture::load("assets/sprites/badguy.png")],
+        sky::Sky::new(texture::Texture::load("assets/sky.png")),
+        settings::Settings::default()
+    );
+
+    renderer.sky.clouds.push(sky::CloudLayer::new(texture::Texture::load("assets/clouds.png"), 0.0, 1.0));
+
+    let mut world = map::Map::new([
+        [1,1,1,1,1,1,1,1,1,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,2,0,0,3,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,3,0,0,2,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,1,1,1,1,1,1,1,1,1]
+    ], 2, 3);
+    for x in 1..5{
+        for y in 1..9{
+            world.set(map::Layer::Ceiling, x, y, 0);
+        }
+    }
+
+    if let (Some(width), Some(height)) = (args.get(2), args.get(3)) {
+        renderer.settings.resolution = (width.parse().unwrap(), height.parse().unwrap());
+        renderer.settings.ray_count = renderer.settings.resolution.0 as usize;
+    }
+
+    renderer.settings.parallel = !serial;
+    if indexed {
+        let textures: Vec<&texture::Texture> = renderer.texture_atlas.iter().chain(renderer.sprite_atlas.iter()).chain(std::iter::once(&renderer.sky.texture)).collect();
+        let palette = palette::Palette::from_textures(&textures);
+        renderer.use_palette(palette, &world.fog);
+    }
+
+    let play = player::Player{
+        plane: (0.0, 0.66),
+        dir: (-1.0, 0.0),
+        pos: (7.5, 4.5),
+        pos_z: player::EYE_HEIGHT,
+        vel_z: 0.0,
+        pitch: 0.0,
+        ih: input_handler::InputHandler::new()
+    };
+    let mut sprites = vec![sprite::Sprite::new((4.5, 4.5), 0)];
+
+    let (frame, _) = renderer.render(&play, &world, &mut sprites);
+    frame.save(&path).unwrap();
+}
@@ -0,0 +1,105 @@
+use crate::sprite::SUPPORTED_ROTATIONS;
+
+/// Whether a clip starts again once it reaches the end or holds its last frame
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Playback{
+    Loop,
+    Once
+}
+
+/// A single frame of an animation clip
+#[derive(Clone)]
+pub struct Frame{
+    /// Atlas entries shown during the frame, either one or one per rotation for directional sprites
+    pub textures: Vec<u8>,
+    /// How long the frame is shown for, in seconds
+    pub duration: f64
+}
+
+impl Frame{
+    pub fn new(texture_index: u8, duration: f64) -> Self{
+        Frame{
+            textures: vec![texture_index],
+            duration
+        }
+    }
+}
+
+/// A sequence of frames such as a walk cycle, an attack or a death. Clips are only made through `Clip::new`,
+/// so every clip has at least one frame
+#[derive(Clone)]
+pub struct Clip{
+    frames: Vec<Frame>,
+    playback: Playback
+}
+
+impl Clip{
+    /// Creates a clip from its frames, None if there are no frames or a frame doesn't have 1, 8 or 16 rotations
+    pub fn new(frames: Vec<Frame>, playback: Playback) -> Option<Self>{
+        if frames.is_empty() || frames.iter().any(|frame| !SUPPORTED_ROTATIONS.contains(&frame.textures.len())) { return None; }
+        Some(Clip{
+            frames,
+            playback
+        })
+    }
+
+    /// Creates a clip that shows each atlas entry in turn for the same amount of time, None if there are no entries
+    pub fn uniform(textures: &[u8], frame_time: f64, playback: Playback) -> Option<Self>{
+        Clip::new(textures.iter().map(|&texture_index| Frame::new(texture_index, frame_time)).collect(), playback)
+    }
+
+    pub fn frames(&self) -> &[Frame]{
+        &self.frames
+    }
+
+    pub fn playback(&self) -> Playback{
+        self.playback
+    }
+
+    /// Total time to play every frame once
+    pub fn length(&self) -> f64{
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Plays a clip, keeping track of how far through it is
+#[derive(Clone)]
+pub struct Animation{
+    pub clip: Clip,
+    pub time: f64
+}
+
+impl Animation{
+    pub fn new(clip: Clip) -> Self{
+        Animation{
+            clip,
+            time: 0.0
+        }
+    }
+
+    /// Moves the animation on, looping clips wrap back to the start
+    pub fn update(&mut self, dt: f64){
+        self.time += dt;
+        let length = self.clip.length();
+        match self.clip.playback{
+            Playback::Loop if length > 0.0 => self.time = self.time.rem_euclid(length),
+            Playback::Once => self.time = self.time.min(length),
+            _ => {}
+        }
+    }
+
+    /// Checks whether a one-shot clip has played to the end, looping clips never finish
+    pub fn is_finished(&self) -> bool{
+        self.clip.playback == Playback::Once && self.time >= self.clip.length()
+    }
+
+    /// Gets the frame being shown, a finished one-shot clip holds its last frame
+    pub fn frame(&self) -> &Frame{
+        let mut end = 0.0;
+        for frame in self.clip.frames.iter(){
+            end += frame.duration;
+            if self.time < end { return frame; }
+        }
+        self.clip.frames.last().expect("Clip::new never makes a clip without frames")
+    }
+}
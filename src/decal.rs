@@ -0,0 +1,61 @@
+use crate::ray::RayHit;
+
+/// How long a decal takes to fade away at the end of its lifetime, in seconds
+const FADE_TIME: f64 = 1.0;
+
+/// An image drawn over one face of a wall, such as a sign, bullet hole or blood splat
+pub struct Decal{
+    /// Map cell of the wall the decal is on
+    pub cell: (i32, i32),
+    /// Face of the wall, using the same numbering as `RayHit::side`
+    pub side: i32,
+    /// Centre of the decal on the face, across the face as a texture position (0.0 to 1.0) and its height above the floor
+    pub pos: (f64, f64),
+    /// Width and height of the decal in world units
+    pub size: (f64, f64),
+    /// Entry in the sprite atlas to draw
+    pub texture_index: u8,
+    /// Time since the decal was placed
+    pub age: f64,
+    /// How long the decal lasts before fading away, None for decals that stay forever such as signs
+    pub lifetime: Option<f64>
+}
+
+impl Decal{
+    /// Creates a permanent decal
+    pub fn new(cell: (i32, i32), side: i32, pos: (f64, f64), size: (f64, f64), texture_index: u8) -> Self{
+        Decal{
+            cell,
+            side,
+            pos,
+            size,
+            texture_index,
+            age: 0.0,
+            lifetime: None
+        }
+    }
+
+    /// Creates a decal where a ray hit a wall at the given height, such as a bullet impact
+    pub fn at_hit(hit: &RayHit, z: f64, size: (f64, f64), texture_index: u8) -> Self{
+        Decal::new(hit.cell, hit.side, (hit.texture_pos, z), size, texture_index)
+    }
+
+    pub fn is_expired(&self) -> bool{
+        matches!(self.lifetime, Some(lifetime) if self.age >= lifetime)
+    }
+
+    /// How visible the decal is, fading from 1.0 to 0.0 over the end of its lifetime
+    pub fn opacity(&self) -> f64{
+        match self.lifetime{
+            Some(lifetime) => ((lifetime - self.age) / FADE_TIME).clamp(0.0, 1.0),
+            None => 1.0
+        }
+    }
+
+    /// Gets the co-ordinate on the decal's texture for a point on its face, if the point is covered by the decal
+    pub fn texture_pos(&self, face_pos: f64, z: f64) -> Option<(f64, f64)>{
+        let u = (face_pos - self.pos.0) / self.size.0 + 0.5;
+        let v = (self.pos.1 - z) / self.size.1 + 0.5;
+        if (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) { Some((u, v)) } else { None }
+    }
+}
@@ -0,0 +1,39 @@
+/// Distance from the camera plane to whatever was drawn at each pixel of a frame, stored column by column.
+/// Pixels where only the sky shows are infinitely far away
+pub struct DepthBuffer{
+    pub width: u32,
+    pub height: u32,
+    depth: Vec<f64>
+}
+
+impl DepthBuffer{
+    /// Creates a buffer where every pixel is infinitely far away
+    pub fn new(width: u32, height: u32) -> Self{
+        DepthBuffer{
+            width,
+            height,
+            depth: vec![f64::INFINITY; (width * height) as usize]
+        }
+    }
+
+    /// Joins the depth of each screen column, left to right, into one buffer
+    pub fn from_columns(height: u32, columns: Vec<Vec<f64>>) -> Self{
+        DepthBuffer{
+            width: columns.len() as u32,
+            height,
+            depth: columns.concat()
+        }
+    }
+
+    /// Gets the depth at a pixel, pixels outside of the frame are infinitely far away
+    pub fn get(&self, x: u32, y: u32) -> f64{
+        if x >= self.width || y >= self.height { return f64::INFINITY; }
+        self.depth[(x * self.height + y) as usize]
+    }
+
+    /// Sets the depth at a pixel, pixels outside of the frame are ignored
+    pub fn set(&mut self, x: u32, y: u32, depth: f64){
+        if x >= self.width || y >= self.height { return; }
+        self.depth[(x * self.height + y) as usize] = depth;
+    }
+}
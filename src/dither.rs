@@ -0,0 +1,26 @@
+use image::RgbaImage;
+
+/// 4x4 ordered Bayer matrix, each cell is the order in which that pixel of a block turns on
+const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5]
+];
+
+/// Gets the dither threshold for a pixel, from -0.5 to just under 0.5 and repeating every 4 pixels
+pub fn threshold(x: u32, y: u32) -> f64{
+    (BAYER[(y % 4) as usize][(x % 4) as usize] as f64 + 0.5) / 16.0 - 0.5
+}
+
+/// Cuts each channel of the frame down to a number of levels, using ordered dithering so smooth shading
+/// shows as a pattern of the nearest levels rather than bands
+pub fn dither(frame: &mut RgbaImage, levels: u32){
+    let step = 255.0 / (levels.max(2) - 1) as f64;
+    for (x, y, pixel) in frame.enumerate_pixels_mut(){
+        let offset = threshold(x, y) * step;
+        for i in 0..3{
+            pixel[i] = (((pixel[i] as f64 + offset) / step).round() * step).clamp(0.0, 255.0) as u8;
+        }
+    }
+}
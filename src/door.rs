@@ -0,0 +1,78 @@
+/// How long a door stays fully open before it starts to close on its own, in seconds
+pub const AUTO_CLOSE_TIME: f64 = 3.0;
+/// How much of the door slides open per second
+const DOOR_SPEED: f64 = 1.2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DoorState{
+    Closed,
+    Opening,
+    Open,
+    Closing
+}
+
+/// A thin sliding door across the middle of a map cell
+pub struct Door{
+    pub pos: (i32, i32),
+    /// True if the door lies across the x axis (halfway along x) so it is hit by rays moving in x,
+    /// false if it lies across the y axis
+    pub vertical: bool,
+    pub texture_index: u8,
+    /// Texture used on the walls either side of the door
+    pub frame_texture: u8,
+    pub state: DoorState,
+    /// How far the door has slid open, from 0.0 (closed) to 1.0 (open)
+    pub offset: f64,
+    /// Time spent fully open, used to close the door automatically
+    pub timer: f64
+}
+
+impl Door{
+    pub fn new(pos: (i32, i32), vertical: bool, texture_index: u8, frame_texture: u8) -> Self{
+        Door{
+            pos,
+            vertical,
+            texture_index,
+            frame_texture,
+            state: DoorState::Closed,
+            offset: 0.0,
+            timer: 0.0
+        }
+    }
+
+    /// Starts opening the door, or holds it open if it already is
+    pub fn open(&mut self){
+        match self.state{
+            DoorState::Closed | DoorState::Closing => self.state = DoorState::Opening,
+            DoorState::Open => self.timer = 0.0,
+            DoorState::Opening => ()
+        }
+    }
+
+    /// Slides the door and closes it once it has been open for long enough. A door will not close on
+    /// something standing in its doorway
+    pub fn update(&mut self, dt: f64, occupied: bool){
+        match self.state{
+            DoorState::Opening => {
+                self.offset += DOOR_SPEED * dt;
+                if self.offset >= 1.0 { self.offset = 1.0; self.timer = 0.0; self.state = DoorState::Open; }
+            }
+            DoorState::Open => {
+                self.timer += dt;
+                if occupied { self.timer = 0.0; }
+                if self.timer >= AUTO_CLOSE_TIME { self.state = DoorState::Closing; }
+            }
+            DoorState::Closing => {
+                if occupied { self.state = DoorState::Opening; return; }
+                self.offset -= DOOR_SPEED * dt;
+                if self.offset <= 0.0 { self.offset = 0.0; self.state = DoorState::Closed; }
+            }
+            DoorState::Closed => ()
+        }
+    }
+
+    /// Doors can only be walked through once they are fully open
+    pub fn is_blocking(&self) -> bool{
+        self.state != DoorState::Open
+    }
+}
@@ -0,0 +1,189 @@
+use std::any::Any;
+use std::f64::consts::TAU;
+
+use image::RgbaImage;
+
+use crate::depth_buffer::DepthBuffer;
+
+/// A full frame effect run over the finished frame, after the world, sprites and sky have been drawn.
+/// Effects are registered with `Renderer::add_effect` and run in the order they were added
+pub trait Effect: Any + Send + Sync{
+    /// Moves the effect on by a time step, for effects that change over time
+    fn update(&mut self, _dt: f64){}
+
+    /// Changes the frame in place, the depth of every pixel is there for effects that need it
+    fn apply(&self, frame: &mut RgbaImage, depth: &DepthBuffer);
+}
+
+/// Gets a pixel, clamping positions outside the frame to its edge
+fn clamped(frame: &RgbaImage, x: i64, y: i64) -> image::Rgba<u8>{
+    *frame.get_pixel(x.clamp(0, frame.width() as i64 - 1) as u32, y.clamp(0, frame.height() as i64 - 1) as u32)
+}
+
+/// Corrects the gamma of the frame then scales its brightness
+pub struct Gamma{
+    /// Values above 1.0 lighten the darker colours
+    pub gamma: f64,
+    /// Multiplies every colour after the gamma is applied
+    pub brightness: f64
+}
+
+impl Effect for Gamma{
+    fn apply(&self, frame: &mut RgbaImage, _depth: &DepthBuffer){
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate(){
+            *value = ((i as f64 / 255.0).powf(1.0 / self.gamma) * self.brightness * 255.0).clamp(0.0, 255.0) as u8;
+        }
+        for pixel in frame.pixels_mut(){
+            for i in 0..3{
+                pixel[i] = table[pixel[i] as usize];
+            }
+        }
+    }
+}
+
+/// Darkens the frame towards its corners
+pub struct Vignette{
+    /// How dark the corners get, from 0.0 to 1.0
+    pub strength: f64,
+    /// Distance from the centre where the darkening starts, where 1.0 is the corners
+    pub radius: f64
+}
+
+impl Effect for Vignette{
+    fn apply(&self, frame: &mut RgbaImage, _depth: &DepthBuffer){
+        let (cx, cy) = (frame.width() as f64 / 2.0, frame.height() as f64 / 2.0);
+        let corner = (cx * cx + cy * cy).sqrt();
+        for (x, y, pixel) in frame.enumerate_pixels_mut(){
+            let dist = ((x as f64 - cx).powi(2) + (y as f64 - cy).powi(2)).sqrt() / corner;
+            let t = ((dist - self.radius) / (1.0 - self.radius).max(f64::EPSILON)).clamp(0.0, 1.0);
+            let shade = 1.0 - self.strength * t * t;
+            for i in 0..3{
+                pixel[i] = (pixel[i] as f64 * shade) as u8;
+            }
+        }
+    }
+}
+
+/// Darkens every few rows like the gaps between the lines of a CRT screen
+pub struct Scanlines{
+    /// How much the dark rows are darkened, from 0.0 to 1.0
+    pub intensity: f64,
+    /// Number of rows from one dark row to the next
+    pub spacing: u32
+}
+
+impl Effect for Scanlines{
+    fn apply(&self, frame: &mut RgbaImage, _depth: &DepthBuffer){
+        let spacing = self.spacing.max(1);
+        for (_, y, pixel) in frame.enumerate_pixels_mut(){
+            if y % spacing != spacing - 1 { continue; }
+            for i in 0..3{
+                pixel[i] = (pixel[i] as f64 * (1.0 - self.intensity)) as u8;
+            }
+        }
+    }
+}
+
+/// Splits the red and blue channels apart towards the sides of the frame, like a cheap lens
+pub struct ChromaticAberration{
+    /// How many pixels the channels are pulled apart at the left and right edges
+    pub offset: f64
+}
+
+impl Effect for ChromaticAberration{
+    fn apply(&self, frame: &mut RgbaImage, _depth: &DepthBuffer){
+        let source = frame.clone();
+        let cx = frame.width() as f64 / 2.0;
+        for (x, y, pixel) in frame.enumerate_pixels_mut(){
+            let shift = (self.offset * (x as f64 - cx) / cx).round() as i64;
+            pixel[0] = clamped(&source, x as i64 - shift, y as i64)[0];
+            pixel[2] = clamped(&source, x as i64 + shift, y as i64)[2];
+        }
+    }
+}
+
+/// Washes the frame with a colour that fades away, such as red when the player is hurt
+pub struct DamageFlash{
+    pub colour: [u8; 3],
+    /// How much of the frame the colour currently covers, from 0.0 to 1.0
+    pub amount: f64,
+    /// How much the amount drops each second
+    pub fade: f64
+}
+
+impl DamageFlash{
+    /// Creates a red flash that fades out over half a second
+    pub fn new() -> Self{
+        DamageFlash{
+            colour: [255, 0, 0],
+            amount: 0.0,
+            fade: 2.0
+        }
+    }
+
+    /// Starts a flash, stronger hits give a larger amount
+    pub fn trigger(&mut self, amount: f64){
+        self.amount = self.amount.max(amount).min(1.0);
+    }
+}
+
+impl Default for DamageFlash{
+    fn default() -> Self{
+        DamageFlash::new()
+    }
+}
+
+impl Effect for DamageFlash{
+    fn update(&mut self, dt: f64){
+        self.amount = (self.amount - self.fade * dt).max(0.0);
+    }
+
+    fn apply(&self, frame: &mut RgbaImage, _depth: &DepthBuffer){
+        if self.amount <= 0.0 { return; }
+        for pixel in frame.pixels_mut(){
+            for i in 0..3{
+                pixel[i] = (pixel[i] as f64 * (1.0 - self.amount) + self.colour[i] as f64 * self.amount) as u8;
+            }
+        }
+    }
+}
+
+/// Bends the frame with slow ripples, as if looking through water
+pub struct Wobble{
+    /// Furthest a pixel is moved, in pixels
+    pub amplitude: f64,
+    /// Length of one ripple, in pixels
+    pub wavelength: f64,
+    /// Ripples passed per second
+    pub speed: f64,
+    pub time: f64
+}
+
+impl Wobble{
+    pub fn new(amplitude: f64, wavelength: f64, speed: f64) -> Self{
+        Wobble{
+            amplitude,
+            wavelength,
+            speed,
+            time: 0.0
+        }
+    }
+}
+
+impl Effect for Wobble{
+    fn update(&mut self, dt: f64){
+        self.time += dt;
+    }
+
+    fn apply(&self, frame: &mut RgbaImage, _depth: &DepthBuffer){
+        let source = frame.clone();
+        let phase = self.time * self.speed * TAU;
+        for (x, y, pixel) in frame.enumerate_pixels_mut(){
+            // Each row sways sideways and each column sways up and down
+            let dx = self.amplitude * (y as f64 / self.wavelength * TAU + phase).sin();
+            let dy = self.amplitude * (x as f64 / self.wavelength * TAU + phase * 0.7).sin();
+            *pixel = clamped(&source, x as i64 + dx.round() as i64, y as i64 + dy.round() as i64);
+        }
+    }
+}
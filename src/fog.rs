@@ -0,0 +1,57 @@
+/// How quickly the fog thickens between its start and end distance
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Falloff{
+    /// Thickens evenly with distance
+    Linear,
+    /// Thickens quickly close to the start and levels off towards the end
+    Exponential,
+    /// Stays thin close to the start then thickens sharply towards the end
+    ExponentialSquared
+}
+
+/// Distance fog shared by the walls, floors and sprites, each level can set its own
+#[derive(Clone, Copy)]
+pub struct Fog{
+    /// Colour everything fades towards, black darkens the world with distance
+    pub colour: [u8; 3],
+    /// Distance where the fog begins
+    pub start: f64,
+    /// Distance where the fog completely hides the world
+    pub end: f64,
+    pub falloff: Falloff
+}
+
+impl Default for Fog{
+    /// Fades to black over the first 10 units, matching the original view distance
+    fn default() -> Self{
+        Fog{
+            colour: [0, 0, 0],
+            start: 0.0,
+            end: 10.0,
+            falloff: Falloff::Linear
+        }
+    }
+}
+
+impl Fog{
+    /// How much of the fog colour covers something at the given distance, from 0.0 (clear) to 1.0 (hidden)
+    pub fn amount(&self, dist: f64) -> f64{
+        let t = ((dist - self.start) / (self.end - self.start).max(f64::EPSILON)).clamp(0.0, 1.0);
+        // The exponential curves are scaled so they still reach full fog at the end distance
+        let full = 1.0 - (-4.0f64).exp();
+        match self.falloff{
+            Falloff::Linear => t,
+            Falloff::Exponential => (1.0 - (-4.0 * t).exp()) / full,
+            Falloff::ExponentialSquared => (1.0 - (-(2.0 * t).powi(2)).exp()) / full
+        }
+    }
+
+    /// Fades the colour of a pixel at the given distance towards the fog colour, keeping its alpha
+    pub fn apply(&self, mut pixel: image::Rgba<u8>, dist: f64) -> image::Rgba<u8>{
+        let amount = self.amount(dist);
+        for i in 0..3{
+            pixel[i] = (pixel[i] as f64 * (1.0 - amount) + self.colour[i] as f64 * amount) as u8;
+        }
+        pixel
+    }
+}
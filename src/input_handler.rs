@@ -2,14 +2,32 @@ use piston::Button;
 
 pub struct InputHandler{
     pub turn: i8,
-    pub adv: i8
+    pub adv: i8,
+    pub look: i8,
+    pub jump: bool,
+    pub crouch: bool,
+    /// Set when the use key is pressed, cleared once the game has acted on it
+    pub interact: bool,
+    /// Set when the fire key is pressed, cleared once the game has acted on it
+    pub fire: bool
+}
+
+impl Default for InputHandler{
+    fn default() -> Self{
+        InputHandler::new()
+    }
 }
 
 impl InputHandler{
     pub fn new() -> Self{
         InputHandler{
             turn: 0,
-            adv: 0
+            adv: 0,
+            look: 0,
+            jump: false,
+            crouch: false,
+            interact: false,
+            fire: false
         }
     }
     
@@ -22,6 +40,12 @@ impl InputHandler{
             Keyboard(Key::Right) => self.turn = 1,
             Keyboard(Key::Up) => self.adv = 1,
             Keyboard(Key::Down) => self.adv = -1,
+            Keyboard(Key::PageUp) => self.look = 1,
+            Keyboard(Key::PageDown) => self.look = -1,
+            Keyboard(Key::Space) => self.jump = true,
+            Keyboard(Key::LCtrl) => self.crouch = true,
+            Keyboard(Key::E) => self.interact = true,
+            Keyboard(Key::F) => self.fire = true,
             _ => (),
         }
     }
@@ -35,7 +59,11 @@ impl InputHandler{
             Keyboard(Key::Right) => self.turn = 0,
             Keyboard(Key::Up) => self.adv = 0,
             Keyboard(Key::Down) => self.adv = 0,
+            Keyboard(Key::PageUp) => self.look = 0,
+            Keyboard(Key::PageDown) => self.look = 0,
+            Keyboard(Key::Space) => self.jump = false,
+            Keyboard(Key::LCtrl) => self.crouch = false,
             _ => (),
         }
     }
-}
\ No newline at end of file
+}
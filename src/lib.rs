@@ -0,0 +1,23 @@
+pub mod animation;
+pub mod app;
+pub mod ray;
+pub mod player;
+pub mod portal;
+pub mod map;
+pub mod mirror;
+pub mod palette;
+pub mod sprite;
+pub mod decal;
+pub mod depth_buffer;
+pub mod dither;
+pub mod door;
+pub mod effect;
+pub mod fog;
+pub mod light;
+pub mod renderer;
+pub mod segment;
+pub mod settings;
+pub mod sky;
+pub mod texture;
+pub mod global;
+pub mod input_handler;
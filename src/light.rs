@@ -0,0 +1,60 @@
+/// A coloured point light in the world such as a torch, lamp or muzzle flash
+pub struct Light{
+    pub pos: (f64, f64),
+    pub colour: [u8; 3],
+    /// Brightness at the centre of the light, 1.0 adds the full colour on top of the ambient light
+    pub intensity: f64,
+    /// Distance at which the light fades out completely
+    pub radius: f64,
+    /// How much the brightness wavers over time, from 0.0 (steady) to 1.0 (can drop to nothing)
+    pub flicker: f64,
+    /// Seconds until the light goes out and is removed from the map, None for lights that stay forever
+    pub lifetime: Option<f64>,
+    /// Time since the light was created, drives the flicker
+    pub time: f64
+}
+
+impl Light{
+    /// Creates a steady light that lasts forever
+    pub fn new(pos: (f64, f64), colour: [u8; 3], intensity: f64, radius: f64) -> Self{
+        Light{
+            pos,
+            colour,
+            intensity,
+            radius,
+            flicker: 0.0,
+            lifetime: None,
+            time: 0.0
+        }
+    }
+
+    /// Moves the flicker along and counts down the lifetime
+    pub fn update(&mut self, dt: f64){
+        self.time += dt;
+        if let Some(lifetime) = self.lifetime.as_mut() {
+            *lifetime -= dt;
+        }
+    }
+
+    pub fn is_expired(&self) -> bool{
+        matches!(self.lifetime, Some(lifetime) if lifetime <= 0.0)
+    }
+
+    /// Current brightness at the centre, a couple of out of step sine waves make the flicker look irregular
+    pub fn brightness(&self) -> f64{
+        let wave = 0.5 + 0.25 * (self.time * 13.0).sin() + 0.25 * (self.time * 7.3 + 1.7).sin();
+        self.intensity * (1.0 - self.flicker * wave)
+    }
+
+    /// Colour this light adds at a point in the world, falling off smoothly to nothing at its radius
+    pub fn contribution(&self, pos: (f64, f64)) -> [f64; 3]{
+        let (dx, dy) = (pos.0 - self.pos.0, pos.1 - self.pos.1);
+        let falloff = (1.0 - (dx * dx + dy * dy).sqrt() / self.radius).max(0.0);
+        let strength = self.brightness() * falloff * falloff;
+        [
+            self.colour[0] as f64 / 255.0 * strength,
+            self.colour[1] as f64 / 255.0 * strength,
+            self.colour[2] as f64 / 255.0 * strength
+        ]
+    }
+}
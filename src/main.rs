@@ -5,23 +5,15 @@ extern crate piston;
 
 use glutin_window::GlutinWindow as Window;
 use graphics::{Image, rectangle};
-use image::GenericImageView;
 use piston::window::WindowSettings;
-use opengl_graphics::{OpenGL, GlGraphics, Texture, TextureSettings};
+use opengl_graphics::{OpenGL, GlGraphics};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{RenderEvent, UpdateEvent};
+use piston::input::{RenderEvent, ResizeEvent, UpdateEvent};
 use piston::{PressEvent, ReleaseEvent};
 
-use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub mod app;
-pub mod ray;
-pub mod player;
-pub mod map;
-pub mod sprite;
-pub mod global;
-pub mod input_handler;
+use raycast_rust::{animation, app, effect, player, map, mirror, palette, portal, sprite, door, light, renderer, segment, settings, sky, texture, global, input_handler};
 
 fn main() {
     // Change this to OpenGL::V2_1 if not working.
@@ -35,30 +27,32 @@ fn main() {
         .unwrap();
 
 
-    let mut texture_atlas: Vec<[[image::Rgba<u8>; 256]; 256]> = Vec::new();
-    let mut sprite_atlas: Vec<[[image::Rgba<u8>; 256]; 256]> = Vec::new();
-
-    /// Converts a dynamic image into a 2d array of pixels (Slightly faster to access, not strictly important)
-    fn new_texture(path: String) -> [[image::Rgba<u8>; 256]; 256] {
-        let mut arr: [[image::Rgba<u8>; 256]; 256] = [[image::Rgba([0,0,0,0]); 256]; 256];
-        let image = image::open(path).unwrap();
-        for i in 0..256{
-            for j in 0..256{
-                let pixel = image.get_pixel(i, j);
-                arr[i as usize][j as usize] = pixel;
-            }
-        }
-        return arr;
-    }
+    let mut texture_atlas: Vec<texture::Texture> = Vec::new();
+    let mut sprite_atlas: Vec<texture::Texture> = Vec::new();
 
     // Load Textures
-    texture_atlas.push(new_texture("assets/brick2.jpg".to_string()));
-    texture_atlas.push(new_texture("assets/wood.jpg".to_string()));
-    texture_atlas.push(new_texture("assets/metal.jpg".to_string()));
+    texture_atlas.push(texture::Texture::load("assets/brick2.jpg"));
+    texture_atlas.push(texture::Texture::load("assets/wood.jpg"));
+    texture_atlas.push(texture::Texture::load("assets/metal.jpg"));
+    texture_atlas.push(texture::Texture::load("assets/grate.png"));
+    // Frames of water flowing down a wall
+    for i in 0..4{
+        texture_atlas.push(texture::Texture::load(&format!("assets/water{}.png", i)));
+    }
 
     // Load Sprites
-    sprite_atlas.push(new_texture("assets/sprites/badguy.png".to_string()));
-    sprite_atlas.push(new_texture("assets/sprites/skulls.png".to_string()));
+    sprite_atlas.push(texture::Texture::load("assets/sprites/badguy.png"));
+    sprite_atlas.push(texture::Texture::load("assets/sprites/skulls.png"));
+    // A guard drawn from 8 angles, starting from the front
+    for i in 0..8{
+        sprite_atlas.push(texture::Texture::load(&format!("assets/sprites/guard{}.png", i)));
+    }
+    // Bullet holes left on the walls when firing
+    sprite_atlas.push(texture::Texture::load("assets/sprites/bullet_hole.png"));
+    // Frames of a burning torch
+    for i in 0..4{
+        sprite_atlas.push(texture::Texture::load(&format!("assets/sprites/flame{}.png", i)));
+    }
 
     // Create a new game and run it.
     let mut app = app::App {
@@ -67,41 +61,139 @@ fn main() {
             plane: (0.0, 0.66),
             dir: (-1.0, 0.0),
             pos: (3.0, 5.0),
-            rays: Vec::new(),
+            pos_z: player::EYE_HEIGHT,
+            vel_z: 0.0,
+            pitch: 0.0,
             ih: input_handler::InputHandler::new()
         },
-        map: map::Map{
-            map_dim: (10, 10),
-            cell_arr: [
+        map: map::Map::new([
                 [1,1,1,1,1,1,1,1,1,1],
                 [1,1,0,1,0,0,1,0,1,1],
                 [1,0,0,0,0,0,0,0,0,1],
                 [3,0,0,0,0,0,0,0,0,1],
                 [1,3,0,1,0,0,1,0,1,1],
-                [1,1,1,1,0,0,1,1,1,1],
+                [1,1,1,1,0,1,1,1,1,1],
                 [1,0,0,0,0,0,0,2,2,1],
                 [1,0,0,0,0,0,0,0,0,1],
                 [1,0,0,0,0,0,0,0,0,1],
                 [1,1,1,3,3,3,3,1,1,1]
-            ]
-        },
+            ], 2, 3),
         sprites: Vec::new(),
-        texture_atlas: texture_atlas,
-        sprite_atlas: sprite_atlas,
-        sky: Texture::from_path(Path::new("assets/sky.png"), &TextureSettings::new()).unwrap(),
+        renderer: renderer::Renderer::new(
+            texture_atlas,
+            sprite_atlas,
+            sky::Sky::new(texture::Texture::load("assets/sky.png")),
+            settings::Settings{ filter: texture::Filter::Trilinear, ..Default::default() }
+        ),
         debug: false,
         last_time_step: SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
         dt: 0.0,
         map_image: Image::new().rect(rectangle::rectangle_by_corners(0.0, 0.0, global::X, global::Y)),
-        sky_image: Image::new().rect(rectangle::rectangle_by_corners(0.0, 0.0, global::X, global::Y/2.0))
+        bullet_hole: 10
     };
 
+    // Drift two layers of cloud across the sky at different speeds, the nearer one wrapping around more often
+    app.renderer.sky.clouds.push(sky::CloudLayer::new(texture::Texture::load("assets/clouds.png"), 0.005, 1.0));
+    app.renderer.sky.clouds.push(sky::CloudLayer::new(texture::Texture::load("assets/clouds.png"), 0.012, 2.0));
+
+    // Open the lower room to the sky and give the corridor leading to it a brick floor
+    for x in 6..9{
+        for y in 1..9{
+            app.map.set(map::Layer::Ceiling, x, y, 0);
+        }
+    }
+    for x in 4..6{
+        for y in 4..6{
+            app.map.set(map::Layer::Floor, x, y, 1);
+        }
+    }
+
+    // Close off the passage between the two rooms with a door
+    app.map.doors.push(door::Door::new((5, 4), true, 2, 3));
+
+    // Fence off the corner of the lower room with a see-through grate
+    for y in 2..4{
+        app.map.set(map::Layer::Wall, 7, y, 4);
+        app.map.set_transparent(7, y, true);
+    }
+
+    // Turn the wooden wall in the lower room into a railing and raise the far wall above the skyline
+    app.map.set_height(6, 7, 0.4);
+    app.map.set_height(6, 8, 0.4);
+    for y in 3..7{
+        app.map.set_height(9, y, 1.6);
+    }
+
+    // Join the metal wall at the end of the upper room to the far corner of the lower room, looking through
+    // or walking into either comes out of the other
+    app.map.link_portals((3, 0), portal::Face::MaxY, (8, 9), portal::Face::MinY);
+
+    // Stand a diamond shaped pillar in the upper room and cut off one of its corners at an angle
+    app.map.segments.push(segment::Segment::new((2.5, 3.2), (2.8, 3.5), 1));
+    app.map.segments.push(segment::Segment::new((2.8, 3.5), (2.5, 3.8), 1));
+    app.map.segments.push(segment::Segment::new((2.5, 3.8), (2.2, 3.5), 1));
+    app.map.segments.push(segment::Segment::new((2.2, 3.5), (2.5, 3.2), 1));
+    app.map.segments.push(segment::Segment::new((2.0, 2.0), (3.0, 1.0), 3));
+
+    // Dim the upper room and light it with a flickering torch
+    for x in 1..5{
+        for y in 1..9{
+            app.map.set_light(x, y, 0.4);
+        }
+    }
+    let mut torch = light::Light::new((2.5, 7.5), [255, 160, 60], 1.5, 3.5);
+    torch.flicker = 0.3;
+    app.map.lights.push(torch);
+    let mut flame = sprite::Sprite::new((2.5, 7.5), 11);
+    flame.scale = (0.4, 0.4);
+    flame.anchor = sprite::Anchor::Centre;
+    flame.play(animation::Clip::uniform(&[11, 12, 13, 14], 0.1, animation::Playback::Loop).unwrap());
+
+    // Run water down the far wall of the upper room
+    app.map.set(map::Layer::Wall, 0, 6, 5);
+    app.map.set(map::Layer::Wall, 0, 7, 5);
+    app.map.animate_texture(5, animation::Clip::uniform(&[5, 6, 7, 8], 0.15, animation::Playback::Loop).unwrap());
+
+    // Hang a slightly blue mirror on the far wall of the upper room, next to the water
+    for y in 4..6{
+        app.map.set(map::Layer::Wall, 0, y, 3);
+        app.map.mirrors.push(mirror::Mirror::new((0, y), [200, 220, 255], 0.85));
+    }
+
     // Create some sprites
-    app.sprites.push(sprite::Sprite{ pos: (8.0, 5.0), texture_index: 0, dist: 0.0});
-    app.sprites.push(sprite::Sprite{ pos: (8.0, 4.0), texture_index: 1, dist: 0.0});
-    app.sprites.push(sprite::Sprite{ pos: (8.0, 6.0), texture_index: 1, dist: 0.0});
+    app.sprites.push(sprite::Sprite::new((8.0, 5.0), 0));
+    app.sprites.push(sprite::Sprite::new((8.0, 4.0), 1));
+    app.sprites.push(sprite::Sprite::new((8.0, 6.0), 1));
+
+    // A small pile of skulls on the floor of the upper room
+    let mut skulls = sprite::Sprite::new((3.5, 1.5), 1);
+    skulls.scale = (0.5, 0.5);
+    app.sprites.push(skulls);
+
+    // A guard in the upper room that looks different from each side as the player walks around it
+    let guard = sprite::SpriteDef::from_textures(&(2..10).collect::<Vec<u8>>()).unwrap();
+    app.sprites.push(sprite::Sprite::directional((2.5, 5.5), 0.0, guard));
+    app.sprites.push(flame);
+
+    // Darken the corners of the screen a little
+    app.renderer.add_effect(effect::Vignette{ strength: 0.4, radius: 0.5 });
+
+    // Run with --retro to draw at half the window size with dithered shading, scaled up by exactly 2 to fill the window
+    if std::env::args().any(|arg| arg == "--retro") {
+        app.renderer.settings.internal_resolution = Some((global::X as u32 / 2, global::Y as u32 / 2));
+        app.renderer.settings.letterbox = true;
+        app.renderer.settings.dither = true;
+    }
 
-    app.sprites.push(sprite::Sprite{ pos: (3.5, 1.5), texture_index: 1, dist: 0.0});
+    // Run with --palette for the 8-bit look, the palette is picked to suit the loaded textures
+    if std::env::args().any(|arg| arg == "--palette") {
+        let textures: Vec<&texture::Texture> = app.renderer.texture_atlas.iter()
+            .chain(app.renderer.sprite_atlas.iter())
+            .chain(std::iter::once(&app.renderer.sky.texture))
+            .collect();
+        let palette = palette::Palette::from_textures(&textures);
+        app.renderer.use_palette(palette, &app.map.fog);
+    }
 
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
@@ -109,6 +201,10 @@ fn main() {
             app.render(&args);
         }
 
+        if let Some(args) = e.resize_args() {
+            app.resize(args.window_size);
+        }
+
         if let Some(_args) = e.update_args() {
             app.update();
         }
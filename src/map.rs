@@ -1,4 +1,279 @@
+use crate::animation::{Animation, Clip};
+use crate::decal::Decal;
+use crate::door::Door;
+use crate::fog::Fog;
+use crate::light::Light;
+use crate::mirror::Mirror;
+use crate::portal::{Face, Portal};
+use crate::segment::Segment;
+
+/// How close the player can get to a line segment wall
+const SEGMENT_CLEARANCE: f64 = 0.2;
+
+/// The layers of the map that can be read and written per cell
+#[derive(Clone, Copy)]
+pub enum Layer{
+    Wall,
+    Floor,
+    Ceiling
+}
+
+/// Grid of cells that make up a level. Each layer stores a texture index per cell where 0 means empty,
+/// for the ceiling layer an empty cell is open to the sky
 pub struct Map{
     pub map_dim: (i32, i32),
-    pub cell_arr: [[u8; 10]; 10]
-}
\ No newline at end of file
+    pub cell_arr: [[u8; 10]; 10],
+    pub floor_arr: [[u8; 10]; 10],
+    pub ceiling_arr: [[u8; 10]; 10],
+    /// Height of the wall in each cell, where 1.0 reaches the ceiling
+    pub height_arr: [[f64; 10]; 10],
+    /// Cells whose wall is see-through wherever its texture has alpha, such as grates and fences
+    pub transparent_arr: [[bool; 10]; 10],
+    /// Ambient light level of each cell, where 1.0 shows textures at their own colour
+    pub light_arr: [[f64; 10]; 10],
+    pub doors: Vec<Door>,
+    /// Wall faces that lead to another face somewhere else in the map
+    pub portals: Vec<Portal>,
+    /// Wall cells whose faces reflect whatever is in front of them
+    pub mirrors: Vec<Mirror>,
+    /// Line segment walls, each part drawn with the height and transparency of the cell it is in
+    pub segments: Vec<Segment>,
+    /// Dynamic point lights such as torches and muzzle flashes
+    pub lights: Vec<Light>,
+    /// Decals drawn over wall faces, oldest first
+    pub decals: Vec<Decal>,
+    /// Most decals the map holds at once, the oldest are removed to make room for new ones
+    pub max_decals: usize,
+    /// Animated textures, every cell using the texture index shows the animation's current frame instead
+    pub texture_animations: Vec<(u8, Animation)>,
+    /// Distance fog used when drawing this level
+    pub fog: Fog
+}
+
+impl Map{
+    /// Creates a map from the given walls with every cell using the same floor and ceiling texture
+    pub fn new(cell_arr: [[u8; 10]; 10], floor: u8, ceiling: u8) -> Self{
+        Map{
+            map_dim: (10, 10),
+            cell_arr,
+            floor_arr: [[floor; 10]; 10],
+            ceiling_arr: [[ceiling; 10]; 10],
+            height_arr: [[1.0; 10]; 10],
+            transparent_arr: [[false; 10]; 10],
+            light_arr: [[1.0; 10]; 10],
+            doors: Vec::new(),
+            portals: Vec::new(),
+            mirrors: Vec::new(),
+            segments: Vec::new(),
+            lights: Vec::new(),
+            decals: Vec::new(),
+            max_decals: 64,
+            texture_animations: Vec::new(),
+            fog: Fog::default()
+        }
+    }
+
+    fn layer(&self, layer: Layer) -> &[[u8; 10]; 10]{
+        match layer{
+            Layer::Wall => &self.cell_arr,
+            Layer::Floor => &self.floor_arr,
+            Layer::Ceiling => &self.ceiling_arr
+        }
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool{
+        x >= 0 && x < self.map_dim.0 && y >= 0 && y < self.map_dim.1
+    }
+
+    /// Gets the texture index of a cell in the given layer, cells outside of the map are empty
+    pub fn get(&self, layer: Layer, x: i32, y: i32) -> u8{
+        if !self.in_bounds(x, y) { return 0; }
+        self.layer(layer)[x as usize][y as usize]
+    }
+
+    /// Sets the texture index of a cell in the given layer, cells outside of the map are ignored
+    pub fn set(&mut self, layer: Layer, x: i32, y: i32, value: u8){
+        if !self.in_bounds(x, y) { return; }
+        let arr = match layer{
+            Layer::Wall => &mut self.cell_arr,
+            Layer::Floor => &mut self.floor_arr,
+            Layer::Ceiling => &mut self.ceiling_arr
+        };
+        arr[x as usize][y as usize] = value;
+    }
+
+    /// Gets the height of the wall in a cell, cells outside of the map have no height
+    pub fn get_height(&self, x: i32, y: i32) -> f64{
+        if !self.in_bounds(x, y) { return 0.0; }
+        self.height_arr[x as usize][y as usize]
+    }
+
+    /// Sets the height of the wall in a cell, cells outside of the map are ignored
+    pub fn set_height(&mut self, x: i32, y: i32, height: f64){
+        if !self.in_bounds(x, y) { return; }
+        self.height_arr[x as usize][y as usize] = height;
+    }
+
+    /// Checks whether the wall in a cell can be seen through
+    pub fn is_transparent(&self, x: i32, y: i32) -> bool{
+        self.in_bounds(x, y) && self.transparent_arr[x as usize][y as usize]
+    }
+
+    /// Marks the wall in a cell as see-through or solid, cells outside of the map are ignored
+    pub fn set_transparent(&mut self, x: i32, y: i32, transparent: bool){
+        if !self.in_bounds(x, y) { return; }
+        self.transparent_arr[x as usize][y as usize] = transparent;
+    }
+
+    /// Gets the ambient light level of a cell, cells outside of the map are unlit
+    pub fn get_light(&self, x: i32, y: i32) -> f64{
+        if !self.in_bounds(x, y) { return 0.0; }
+        self.light_arr[x as usize][y as usize]
+    }
+
+    /// Sets the ambient light level of a cell, cells outside of the map are ignored
+    pub fn set_light(&mut self, x: i32, y: i32, level: f64){
+        if !self.in_bounds(x, y) { return; }
+        self.light_arr[x as usize][y as usize] = level;
+    }
+
+    /// Gets the colour of the light at a point in the world as a multiplier per channel,
+    /// the ambient light of the cell plus every point light that reaches it
+    pub fn light_at(&self, pos: (f64, f64)) -> [f64; 3]{
+        let ambient = self.get_light(pos.0.floor() as i32, pos.1.floor() as i32);
+        let mut light = [ambient; 3];
+        for point in self.lights.iter(){
+            let added = point.contribution(pos);
+            for i in 0..3{
+                light[i] += added[i];
+            }
+        }
+        light
+    }
+
+    /// Moves every point light's flicker along and removes the ones that have burnt out
+    pub fn update_lights(&mut self, dt: f64){
+        for light in self.lights.iter_mut(){
+            light.update(dt);
+        }
+        self.lights.retain(|light| !light.is_expired());
+    }
+
+    /// Places a decal on a wall, removing the oldest decals if there are more than `max_decals`
+    pub fn add_decal(&mut self, decal: Decal){
+        self.decals.push(decal);
+        if self.decals.len() > self.max_decals {
+            self.decals.drain(..self.decals.len() - self.max_decals);
+        }
+    }
+
+    /// Ages every decal and removes the ones that have faded away
+    pub fn update_decals(&mut self, dt: f64){
+        for decal in self.decals.iter_mut(){
+            decal.age += dt;
+        }
+        self.decals.retain(|decal| !decal.is_expired());
+    }
+
+    /// Animates every wall, floor and ceiling using a texture index with a looping clip such as flowing water
+    pub fn animate_texture(&mut self, texture_index: u8, clip: Clip){
+        self.texture_animations.retain(|(ti, _)| *ti != texture_index);
+        self.texture_animations.push((texture_index, Animation::new(clip)));
+    }
+
+    /// Gets the texture index to draw for a cell's texture index, following any animation it has
+    pub fn texture(&self, texture_index: u8) -> u8{
+        match self.texture_animations.iter().find(|(ti, _)| *ti == texture_index){
+            Some((_, animation)) => animation.frame().textures[0],
+            None => texture_index
+        }
+    }
+
+    /// Moves every animated texture on
+    pub fn update_animations(&mut self, dt: f64){
+        for (_, animation) in self.texture_animations.iter_mut(){
+            animation.update(dt);
+        }
+    }
+
+    /// Gets the height of the tallest wall in the map, any ray that hits a wall this tall can stop
+    pub fn max_height(&self) -> f64{
+        let mut max = 0.0;
+        for x in 0..self.map_dim.0{
+            for y in 0..self.map_dim.1{
+                if self.get(Layer::Wall, x, y) > 0 && self.get_height(x, y) > max { max = self.get_height(x, y); }
+            }
+        }
+        for x in 0..self.map_dim.0{
+            for y in 0..self.map_dim.1{
+                if self.segments_in(x, y).next().is_some() && self.get_height(x, y) > max { max = self.get_height(x, y); }
+            }
+        }
+        max
+    }
+
+    /// Gets the door in a cell if there is one
+    pub fn door_at(&self, x: i32, y: i32) -> Option<&Door>{
+        self.doors.iter().find(|door| door.pos == (x, y))
+    }
+
+    /// Gets the door in a cell if there is one so that it can be opened
+    pub fn door_at_mut(&mut self, x: i32, y: i32) -> Option<&mut Door>{
+        self.doors.iter_mut().find(|door| door.pos == (x, y))
+    }
+
+    /// Gets the portal on a face of a wall cell if there is one
+    pub fn portal_at(&self, x: i32, y: i32, face: Face) -> Option<&Portal>{
+        self.portals.iter().find(|portal| portal.cell == (x, y) && portal.face == face)
+    }
+
+    /// Gets the mirror in a wall cell if there is one
+    pub fn mirror_at(&self, x: i32, y: i32) -> Option<&Mirror>{
+        self.mirrors.iter().find(|mirror| mirror.cell == (x, y))
+    }
+
+    /// Links two wall faces with a portal each way, so walking into either comes out of the other
+    pub fn link_portals(&mut self, a: (i32, i32), a_face: Face, b: (i32, i32), b_face: Face){
+        self.portals.push(Portal::new(a, a_face, b, b_face));
+        self.portals.push(Portal::new(b, b_face, a, a_face));
+    }
+
+    /// Finds the portal the player would walk into when moving between two points, if any
+    pub fn portal_between(&self, from: (f64, f64), to: (f64, f64)) -> Option<&Portal>{
+        let (from_x, from_y) = (from.0.floor() as i32, from.1.floor() as i32);
+        let (to_x, to_y) = (to.0.floor() as i32, to.1.floor() as i32);
+        let face = match (to_x - from_x, to_y - from_y){
+            (1, 0) => Face::MinX,
+            (-1, 0) => Face::MaxX,
+            (0, 1) => Face::MinY,
+            (0, -1) => Face::MaxY,
+            _ => return None
+        };
+        self.portal_at(to_x, to_y, face)
+    }
+
+    /// Gets the line segment walls that pass through a cell, including ones that carry on into other cells
+    pub fn segments_in(&self, x: i32, y: i32) -> impl Iterator<Item = &Segment>{
+        self.segments.iter().filter(move |segment| segment.touches_cell((x, y)))
+    }
+
+    /// Checks whether moving between two points would walk through a line segment wall or too close to one,
+    /// moving away from a segment is always allowed so the player can't get stuck
+    pub fn segment_blocks(&self, from: (f64, f64), to: (f64, f64)) -> bool{
+        let step = (to.0 - from.0, to.1 - from.1);
+        self.segments.iter().any(|segment| {
+            let crosses = matches!(segment.intersect(from, step), Some((t, _)) if t <= 1.0);
+            let dist = segment.distance_to(to);
+            crosses || (dist < SEGMENT_CLEARANCE && dist < segment.distance_to(from))
+        })
+    }
+
+    /// Checks whether a cell can be walked into, cells outside of the map are always blocked
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool{
+        if !self.in_bounds(x, y) || self.get(Layer::Wall, x, y) > 0 { return true; }
+        match self.door_at(x, y){
+            Some(door) => door.is_blocking(),
+            None => false
+        }
+    }
+}
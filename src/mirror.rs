@@ -0,0 +1,33 @@
+/// A wall cell that reflects whatever is in front of it, drawn as its wall texture blended over the reflection
+pub struct Mirror{
+    pub cell: (i32, i32),
+    /// Colour the reflection is multiplied by, white leaves it unchanged
+    pub tint: [u8; 3],
+    /// How much of the reflection shows over the wall texture, from 0.0 to 1.0
+    pub reflectivity: f64
+}
+
+impl Mirror{
+    pub fn new(cell: (i32, i32), tint: [u8; 3], reflectivity: f64) -> Self{
+        Mirror{
+            cell,
+            tint,
+            reflectivity
+        }
+    }
+
+    /// Mixes the mirror's own surface with the tinted colour reflected in it. Where nothing has been drawn
+    /// in the reflection yet the result stays partly transparent so the sky can fill it in
+    pub fn reflect(&self, surface: image::Rgba<u8>, reflected: image::Rgba<u8>) -> image::Rgba<u8> {
+        let seen = self.reflectivity * reflected[3] as f64 / 255.0;
+        let alpha = 1.0 - self.reflectivity + seen;
+        if alpha <= 0.0 { return image::Rgba([0, 0, 0, 0]); }
+        let mut pixel = surface;
+        for i in 0..3{
+            let tinted = reflected[i] as f64 * self.tint[i] as f64 / 255.0;
+            pixel[i] = ((surface[i] as f64 * (1.0 - self.reflectivity) + tinted * seen) / alpha) as u8;
+        }
+        pixel[3] = (alpha * 255.0) as u8;
+        pixel
+    }
+}
@@ -0,0 +1,193 @@
+use crate::dither;
+use crate::texture::Texture;
+
+/// Number of rows in the light colormap, spread evenly from black up to `MAX_BRIGHTNESS`
+pub const LIGHT_LEVELS: usize = 64;
+/// Brightest light the colormap covers, brighter light is clamped to it
+pub const MAX_BRIGHTNESS: f64 = 2.0;
+/// Number of rows in the fog colormap, from clear to completely fogged
+pub const FOG_LEVELS: usize = 32;
+/// How far dithering pushes a colour either way before it is matched to the palette
+const DITHER_SPREAD: f64 = 24.0;
+
+/// A table that maps every palette index to another one, such as the same colour darkened
+type Colormap = [u8; 256];
+
+/// A 256 colour palette for indexed colour rendering. Lighting and fog are looked up in colormaps built
+/// from the palette instead of being worked out per channel, and the colours shown on screen can be swapped
+/// for a tinted set for effects like damage flashes and pickups
+pub struct Palette{
+    pub colours: Vec<[u8; 3]>,
+    /// Nearest palette index for every colour, with 5 bits per channel
+    lookup: Vec<u8>,
+    /// The colormap for each light level
+    light: Vec<Colormap>,
+    /// The colormap for each amount of fog
+    fog: Vec<Colormap>,
+    /// Colour the fog colormaps fade towards
+    fog_colour: [u8; 3],
+    /// Colours shown on screen for each index, the palette itself unless a swap is applied
+    display: Vec<[u8; 3]>
+}
+
+impl Palette{
+    /// Creates a palette from up to 256 colours, unused entries are filled with black
+    pub fn new(colours: &[[u8; 3]]) -> Self{
+        let mut colours = colours.to_vec();
+        colours.resize(256, [0, 0, 0]);
+
+        let mut lookup = Vec::with_capacity(32 * 32 * 32);
+        for r in 0..32u32{
+            for g in 0..32u32{
+                for b in 0..32u32{
+                    let centre = [(r * 8 + 4) as u8, (g * 8 + 4) as u8, (b * 8 + 4) as u8];
+                    lookup.push(Palette::nearest(&colours, centre));
+                }
+            }
+        }
+
+        let mut palette = Palette{
+            display: colours.clone(),
+            colours,
+            lookup,
+            light: Vec::new(),
+            fog: Vec::new(),
+            fog_colour: [0, 0, 0]
+        };
+        palette.light = (0..LIGHT_LEVELS).map(|level| {
+            let brightness = level as f64 / (LIGHT_LEVELS - 1) as f64 * MAX_BRIGHTNESS;
+            palette.colormap(|colour| colour.map(|c| (c as f64 * brightness).min(255.0) as u8))
+        }).collect();
+        palette.set_fog_colour([0, 0, 0]);
+        palette
+    }
+
+    /// Builds a palette that suits a set of textures by splitting their colours into 256 groups
+    /// along whichever channel varies most (median cut) and taking the average colour of each group
+    pub fn from_textures(textures: &[&Texture]) -> Self{
+        let mut pixels: Vec<[u8; 3]> = Vec::new();
+        for texture in textures{
+            // Larger textures are thinned out so every texture gets a say without the palette taking an age to build
+            let step = ((texture.width * texture.height) as f64 / 4096.0).sqrt().max(1.0) as u32;
+            for x in (0..texture.width).step_by(step as usize){
+                for y in (0..texture.height).step_by(step as usize){
+                    let pixel = texture.get(x, y);
+                    if pixel[3] > 0 { pixels.push([pixel[0], pixel[1], pixel[2]]); }
+                }
+            }
+        }
+        if pixels.is_empty() { return Palette::new(&[]); }
+
+        let mut boxes = vec![pixels];
+        while boxes.len() < 256 {
+            // Split the group with the widest spread of any channel
+            let (index, channel, range) = boxes.iter().enumerate()
+                .map(|(i, pixels)| {
+                    let (channel, range) = (0..3)
+                        .map(|c| (c, pixels.iter().map(|p| p[c]).max().unwrap() - pixels.iter().map(|p| p[c]).min().unwrap()))
+                        .max_by_key(|&(_, range)| range).unwrap();
+                    (i, channel, range)
+                })
+                .max_by_key(|&(_, _, range)| range).unwrap();
+            if range == 0 { break; }
+            let mut group = boxes.swap_remove(index);
+            group.sort_unstable_by_key(|p| p[channel]);
+            let upper = group.split_off(group.len() / 2);
+            boxes.push(group);
+            boxes.push(upper);
+        }
+
+        let colours: Vec<[u8; 3]> = boxes.iter().map(|pixels| {
+            let mut sum = [0u64; 3];
+            for pixel in pixels{
+                for i in 0..3{
+                    sum[i] += pixel[i] as u64;
+                }
+            }
+            sum.map(|s| (s / pixels.len() as u64) as u8)
+        }).collect();
+        Palette::new(&colours)
+    }
+
+    /// Searches the whole palette for the closest colour
+    fn nearest(colours: &[[u8; 3]], colour: [u8; 3]) -> u8{
+        let dist = |c: &[u8; 3]| (0..3).map(|i| (c[i] as i32 - colour[i] as i32).pow(2)).sum::<i32>();
+        colours.iter().enumerate().min_by_key(|(_, c)| dist(c)).map_or(0, |(i, _)| i as u8)
+    }
+
+    /// Builds a colormap by changing each palette colour and finding the index closest to the result
+    fn colormap(&self, change: impl Fn([u8; 3]) -> [u8; 3]) -> Colormap{
+        let mut map = [0; 256];
+        for (i, colour) in self.colours.iter().enumerate(){
+            map[i] = self.index(change(*colour));
+        }
+        map
+    }
+
+    /// Gets the palette index closest to a colour
+    pub fn index(&self, colour: [u8; 3]) -> u8{
+        let (r, g, b) = ((colour[0] >> 3) as usize, (colour[1] >> 3) as usize, (colour[2] >> 3) as usize);
+        self.lookup[(r * 32 + g) * 32 + b]
+    }
+
+    /// Replaces the colour of a pixel with the closest one in the palette, keeping its alpha
+    pub fn quantize(&self, mut pixel: image::Rgba<u8>) -> image::Rgba<u8>{
+        let colour = self.colours[self.index([pixel[0], pixel[1], pixel[2]]) as usize];
+        pixel[0] = colour[0];
+        pixel[1] = colour[1];
+        pixel[2] = colour[2];
+        pixel
+    }
+
+    /// Rebuilds the fog colormaps to fade towards a new colour, this only needs doing when a level's fog changes
+    pub fn set_fog_colour(&mut self, colour: [u8; 3]){
+        self.fog_colour = colour;
+        self.fog = (0..FOG_LEVELS).map(|level| {
+            let amount = level as f64 / (FOG_LEVELS - 1) as f64;
+            self.colormap(|c| [0, 1, 2].map(|i| (c[i] as f64 * (1.0 - amount) + colour[i] as f64 * amount) as u8))
+        }).collect();
+    }
+
+    pub fn fog_colour(&self) -> [u8; 3]{
+        self.fog_colour
+    }
+
+    /// Lights an index by a brightness, where 1.0 leaves it unchanged
+    pub fn light(&self, index: u8, brightness: f64) -> u8{
+        let level = (brightness / MAX_BRIGHTNESS * (LIGHT_LEVELS - 1) as f64).round().clamp(0.0, (LIGHT_LEVELS - 1) as f64);
+        self.light[level as usize][index as usize]
+    }
+
+    /// Fades an index into the fog by an amount from 0.0 (clear) to 1.0 (hidden)
+    pub fn fog(&self, index: u8, amount: f64) -> u8{
+        let level = (amount * (FOG_LEVELS - 1) as f64).round().clamp(0.0, (FOG_LEVELS - 1) as f64);
+        self.fog[level as usize][index as usize]
+    }
+
+    /// Gets the colour shown on screen for an index, which follows any palette swap
+    pub fn display(&self, index: u8) -> [u8; 3]{
+        self.display[index as usize]
+    }
+
+    /// Turns a finished frame into the colours shown on screen. Pixels that were blended or filtered between
+    /// palette colours are snapped back to the palette first, with ordered dithering when `dither` is set
+    pub fn present(&self, img: &mut image::RgbaImage, dither: bool){
+        for (x, y, pixel) in img.enumerate_pixels_mut(){
+            let offset = if dither { dither::threshold(x, y) * DITHER_SPREAD } else { 0.0 };
+            let colour = pixel.0.map(|c| (c as f64 + offset).clamp(0.0, 255.0) as u8);
+            let colour = self.display(self.index([colour[0], colour[1], colour[2]]));
+            pixel[0] = colour[0];
+            pixel[1] = colour[1];
+            pixel[2] = colour[2];
+        }
+    }
+
+    /// Swaps the colours shown on screen for the palette blended towards a tint, such as red for a damage flash
+    /// or gold for a pickup. An `amount` of 0.0 puts the original palette back
+    pub fn swap(&mut self, tint: [u8; 3], amount: f64){
+        let amount = amount.clamp(0.0, 1.0);
+        self.display = self.colours.iter()
+            .map(|c| [0, 1, 2].map(|i| (c[i] as f64 * (1.0 - amount) + tint[i] as f64 * amount) as u8))
+            .collect();
+    }
+}
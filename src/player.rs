@@ -1,18 +1,50 @@
-use crate::ray;
+use crate::map;
 use crate::input_handler::InputHandler;
+use crate::portal::Portal;
+
+/// Height of the camera above the floor when standing, walls are one unit tall
+pub const EYE_HEIGHT: f64 = 0.5;
+/// Height of the camera above the floor when crouching
+pub const CROUCH_HEIGHT: f64 = 0.3;
+const JUMP_SPEED: f64 = 2.5;
+const GRAVITY: f64 = 9.0;
+const STAND_SPEED: f64 = 1.5;
+const LOOK_SPEED: f64 = 0.8;
+
 pub struct Player{
     pub plane: (f64, f64),
     pub dir: (f64, f64),
     pub pos: (f64, f64),
-    pub rays: Vec<ray::Ray>,
+    /// Height of the camera above the floor
+    pub pos_z: f64,
+    /// Vertical speed of the camera, used for jumping
+    pub vel_z: f64,
+    /// How far the horizon is shifted down the screen as a fraction of its height, positive values look up
+    pub pitch: f64,
     pub ih: InputHandler
 }
 
 impl Player {
-    pub fn advance(&mut self, amount: f64, dt: f64, direction: f64, map: &[[u8; 10]; 10]) {
+    /// Moves the player along the direction they face, one axis at a time so they slide along walls and line segments.
+    /// Walking into a portal carries the player out of its target
+    pub fn advance(&mut self, amount: f64, dt: f64, direction: f64, map: &map::Map) {
         let a = amount*dt;
-        if map[(self.pos.0 + (self.dir.0 * direction) * a )as usize][self.pos.1 as usize] == 0 { self.pos.0 += self.dir.0 * a * direction; }
-        if map[self.pos.0 as usize][(self.pos.1 + (self.dir.1 * direction) * a) as usize] == 0 { self.pos.1 += self.dir.1 * a * direction; }
+        let next_x = (self.pos.0 + (self.dir.0 * direction) * a, self.pos.1);
+        if let Some(portal) = map.portal_between(self.pos, next_x) { return self.teleport(portal, next_x, map); }
+        if !map.is_blocked(next_x.0 as i32, next_x.1 as i32) && !map.segment_blocks(self.pos, next_x) { self.pos = next_x; }
+        let next_y = (self.pos.0, self.pos.1 + (self.dir.1 * direction) * a);
+        if let Some(portal) = map.portal_between(self.pos, next_y) { return self.teleport(portal, next_y, map); }
+        if !map.is_blocked(next_y.0 as i32, next_y.1 as i32) && !map.segment_blocks(self.pos, next_y) { self.pos = next_y; }
+    }
+
+    /// Moves the player to where a point inside a portal comes out of its target, turning them to match,
+    /// unless something is blocking the other side
+    fn teleport(&mut self, portal: &Portal, pos: (f64, f64), map: &map::Map) {
+        let exit = portal.transform_point(pos);
+        if map.is_blocked(exit.0.floor() as i32, exit.1.floor() as i32) { return; }
+        self.pos = exit;
+        self.dir = portal.transform_dir(self.dir);
+        self.plane = portal.transform_dir(self.plane);
     }
 
     pub fn turn(&mut self, amount: f64, dt: f64) {
@@ -26,7 +58,41 @@ impl Player {
         self.plane.1 = old_plane_x * a.sin() + self.plane.1 * a.cos();
     }
 
-    pub fn update(&mut self, dt: f64, map: &[[u8; 10]; 10]){
+    /// Tilts the view up or down by shearing the horizon, clamped so the horizon stays on screen
+    pub fn look(&mut self, amount: f64, dt: f64) {
+        self.pitch = (self.pitch + amount * dt).clamp(-0.5, 0.5);
+    }
+
+    /// Moves the camera height towards standing or crouching, and applies gravity while jumping
+    pub fn update_height(&mut self, dt: f64) {
+        let ground = if self.ih.crouch { CROUCH_HEIGHT } else { EYE_HEIGHT };
+        if self.ih.jump && self.vel_z == 0.0 && self.pos_z <= ground { self.vel_z = JUMP_SPEED; }
+
+        if self.vel_z != 0.0 || self.pos_z > ground {
+            // In the air (or dropping into a crouch) so fall until the ground is reached
+            self.vel_z -= GRAVITY * dt;
+            self.pos_z += self.vel_z * dt;
+            if self.pos_z <= ground { self.pos_z = ground; self.vel_z = 0.0; }
+        }
+        else if self.pos_z < ground {
+            // Standing back up from a crouch
+            self.pos_z = (self.pos_z + STAND_SPEED * dt).min(ground);
+        }
+        // Keep the camera between the floor and the ceiling
+        self.pos_z = self.pos_z.clamp(0.05, 0.95);
+    }
+
+    /// Gets the screen row of the horizon for a screen of the given height
+    pub fn horizon(&self, height: f64) -> f64 {
+        height * (0.5 + self.pitch)
+    }
+
+    /// Gets the cell directly in front of the player
+    pub fn facing_cell(&self) -> (i32, i32) {
+        ((self.pos.0 + self.dir.0).floor() as i32, (self.pos.1 + self.dir.1).floor() as i32)
+    }
+
+    pub fn update(&mut self, dt: f64, map: &map::Map){
         match self.ih.turn{
             -1 => self.turn(3.0, dt),
             1 => self.turn(-3.0, dt),
@@ -37,5 +103,11 @@ impl Player {
             1 => self.advance(2.0, 1.0,  dt, map),
             _ => ()
         }
+        match self.ih.look{
+            -1 => self.look(-LOOK_SPEED, dt),
+            1 => self.look(LOOK_SPEED, dt),
+            _ => ()
+        }
+        self.update_height(dt);
     }
-}
\ No newline at end of file
+}
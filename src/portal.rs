@@ -0,0 +1,91 @@
+use crate::ray::Unfold;
+
+/// One of the four faces of a map cell, named by the edge of the cell it lies on
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Face{
+    MinX,
+    MaxX,
+    MinY,
+    MaxY
+}
+
+impl Face{
+    /// Gets the face of a wall that a ray hits, from the `side` recorded in its `RayHit`
+    pub fn from_side(side: i32) -> Face{
+        match side{
+            0 => Face::MaxX,
+            1 => Face::MinX,
+            2 => Face::MaxY,
+            _ => Face::MinY
+        }
+    }
+
+    /// Direction pointing out of the cell through the face
+    pub fn normal(self) -> (f64, f64){
+        match self{
+            Face::MinX => (-1.0, 0.0),
+            Face::MaxX => (1.0, 0.0),
+            Face::MinY => (0.0, -1.0),
+            Face::MaxY => (0.0, 1.0)
+        }
+    }
+
+    /// Gets the middle of the face on a cell
+    fn centre(self, cell: (i32, i32)) -> (f64, f64){
+        let normal = self.normal();
+        (cell.0 as f64 + 0.5 + normal.0 * 0.5, cell.1 as f64 + 0.5 + normal.1 * 0.5)
+    }
+}
+
+/// A wall face that leads somewhere else in the map. Anything going into the face comes out of the target face,
+/// turned so that it leaves the target in the direction the target faces
+pub struct Portal{
+    pub cell: (i32, i32),
+    pub face: Face,
+    pub target: (i32, i32),
+    pub target_face: Face
+}
+
+impl Portal{
+    pub fn new(cell: (i32, i32), face: Face, target: (i32, i32), target_face: Face) -> Self{
+        Portal{
+            cell,
+            face,
+            target,
+            target_face
+        }
+    }
+
+    /// Cosine and sine of the angle that turns the way into the portal into the way out of the target
+    fn rotation(&self) -> (f64, f64){
+        let (ix, iy) = self.face.normal();
+        let (ix, iy) = (-ix, -iy);
+        let (ox, oy) = self.target_face.normal();
+        (ix * ox + iy * oy, ix * oy - iy * ox)
+    }
+
+    /// Turns a direction going into the portal into the direction it comes out of the target
+    pub fn transform_dir(&self, dir: (f64, f64)) -> (f64, f64){
+        let (cos, sin) = self.rotation();
+        (dir.0 * cos - dir.1 * sin, dir.0 * sin + dir.1 * cos)
+    }
+
+    /// Gets the movement from the portal to its target as an unfold, used to see things through the portal
+    pub fn transform(&self) -> Unfold{
+        let (cos, sin) = self.rotation();
+        let (from, to) = (self.face.centre(self.cell), self.target_face.centre(self.target));
+        let turned = self.transform_dir(from);
+        Unfold{
+            x_axis: (cos, sin),
+            y_axis: (-sin, cos),
+            offset: (to.0 - turned.0, to.1 - turned.1)
+        }
+    }
+
+    /// Moves a point near the portal to the same place relative to the target face
+    pub fn transform_point(&self, point: (f64, f64)) -> (f64, f64){
+        let (from, to) = (self.face.centre(self.cell), self.target_face.centre(self.target));
+        let offset = self.transform_dir((point.0 - from.0, point.1 - from.1));
+        (to.0 + offset.0, to.1 + offset.1)
+    }
+}
@@ -1,6 +1,130 @@
-pub struct Ray{
+use crate::player;
+
+/// A single wall face that a ray passed through
+pub struct RayHit{
     pub length: f64,
+    /// Distance at which the ray leaves the wall's cell, used to draw the top of walls below the camera
+    pub exit_length: f64,
     pub texture_index: u8,
-    pub texture_pos: i32,
-    pub side: i32
-}
\ No newline at end of file
+    /// Horizontal position on the wall's texture, from 0.0 to 1.0
+    pub texture_pos: f64,
+    pub side: i32,
+    pub height: f64,
+    /// Map cell of the wall that was hit
+    pub cell: (i32, i32),
+    /// Point in the world just in front of the wall face, used to light the wall from the cell the ray came from
+    pub pos: (f64, f64),
+    /// See-through walls let the ray carry on and are blended over whatever is behind them
+    pub transparent: bool,
+    /// Mirrors bounce the ray and are blended over whatever it sees in them
+    pub mirror: bool
+}
+
+impl RayHit{
+    /// Gets the top and bottom screen rows of the wall, and the height in pixels of one world unit at its distance
+    pub fn projection(&self, play: &player::Player, screen_height: f64) -> (f64, f64, f64){
+        let horizon = play.horizon(screen_height);
+        let h = screen_height / self.length;
+        (horizon - (self.height - play.pos_z) * h, horizon + play.pos_z * h, h)
+    }
+}
+
+/// Maps a point in the space a leg travels through to where it appears to the player, as if the ray had carried on
+/// in a straight line. Each portal and mirror the ray goes through adds to it
+#[derive(Clone, Copy, PartialEq)]
+pub struct Unfold{
+    /// Where the x and y axes end up
+    pub x_axis: (f64, f64),
+    pub y_axis: (f64, f64),
+    pub offset: (f64, f64)
+}
+
+impl Unfold{
+    pub const IDENTITY: Unfold = Unfold{ x_axis: (1.0, 0.0), y_axis: (0.0, 1.0), offset: (0.0, 0.0) };
+
+    /// Reflects across the line x = `x`
+    pub fn mirror_x(x: f64) -> Self{
+        Unfold{ x_axis: (-1.0, 0.0), y_axis: (0.0, 1.0), offset: (2.0 * x, 0.0) }
+    }
+
+    /// Reflects across the line y = `y`
+    pub fn mirror_y(y: f64) -> Self{
+        Unfold{ x_axis: (1.0, 0.0), y_axis: (0.0, -1.0), offset: (0.0, 2.0 * y) }
+    }
+
+    /// Maps a direction, which turns or flips it without the offset
+    pub fn apply_dir(&self, dir: (f64, f64)) -> (f64, f64){
+        (self.x_axis.0 * dir.0 + self.y_axis.0 * dir.1, self.x_axis.1 * dir.0 + self.y_axis.1 * dir.1)
+    }
+
+    pub fn apply(&self, point: (f64, f64)) -> (f64, f64){
+        let (x, y) = self.apply_dir(point);
+        (x + self.offset.0, y + self.offset.1)
+    }
+
+    /// Combines two unfolds into one that applies `inner` first
+    pub fn then(&self, inner: &Unfold) -> Unfold{
+        Unfold{
+            x_axis: self.apply_dir(inner.x_axis),
+            y_axis: self.apply_dir(inner.y_axis),
+            offset: self.apply(inner.offset)
+        }
+    }
+
+    pub fn inverse(&self) -> Unfold{
+        let det = self.x_axis.0 * self.y_axis.1 - self.y_axis.0 * self.x_axis.1;
+        let linear = Unfold{
+            x_axis: (self.y_axis.1 / det, -self.x_axis.1 / det),
+            y_axis: (-self.y_axis.0 / det, self.x_axis.0 / det),
+            offset: (0.0, 0.0)
+        };
+        let (x, y) = linear.apply_dir(self.offset);
+        Unfold{ offset: (-x, -y), ..linear }
+    }
+
+    /// Checks whether the unfold flips things over, as seeing them in a mirror does
+    pub fn is_mirrored(&self) -> bool{
+        self.x_axis.0 * self.y_axis.1 - self.y_axis.0 * self.x_axis.1 < 0.0
+    }
+}
+
+/// A straight part of a ray, a new one starts each time the ray passes through a portal or bounces off a mirror
+#[derive(Clone, Copy)]
+pub struct Leg{
+    /// Distance along the ray where the leg starts
+    pub start: f64,
+    pub origin: (f64, f64),
+    pub dir: (f64, f64),
+    pub unfold: Unfold
+}
+
+/// The column of the world seen along one ray. Walls lower than the tallest wall in the map do not stop
+/// the ray, so it can pass through several of them before it ends
+pub struct Ray{
+    pub dir: (f64, f64),
+    /// Every wall the ray crossed, ordered from nearest to furthest
+    pub hits: Vec<RayHit>,
+    /// The straight parts of the ray, starting with the one leaving the player
+    pub legs: Vec<Leg>
+}
+
+impl Ray{
+    /// Gets the distance to the furthest wall the ray hit
+    pub fn length(&self) -> f64{
+        match self.hits.last(){
+            Some(hit) => hit.length,
+            None => f64::MAX
+        }
+    }
+
+    /// Gets the leg of the ray that a distance along it falls on
+    pub fn leg_at(&self, dist: f64) -> &Leg{
+        self.legs.iter().rev().find(|leg| leg.start <= dist).unwrap_or(&self.legs[0])
+    }
+
+    /// Gets the point in the world a distance along the ray, following it through any portals
+    pub fn point_at(&self, dist: f64) -> (f64, f64){
+        let leg = self.leg_at(dist);
+        (leg.origin.0 + (dist - leg.start) * leg.dir.0, leg.origin.1 + (dist - leg.start) * leg.dir.1)
+    }
+}
@@ -0,0 +1,799 @@
+use image::{ImageBuffer, RgbaImage};
+use rayon::prelude::*;
+
+use crate::decal::Decal;
+use crate::depth_buffer::DepthBuffer;
+use crate::dither;
+use crate::effect::Effect;
+use crate::fog::Fog;
+use crate::palette::Palette;
+use crate::player;
+use crate::portal::Face;
+use crate::settings::Settings;
+use crate::sky::Sky;
+use crate::texture::Texture;
+use crate::map;
+use crate::mirror::Mirror;
+use crate::segment::Segment;
+use crate::ray;
+use crate::sprite;
+
+/// Most portals and mirrors a single ray can pass through or bounce off, stops rays going forever between
+/// portals or mirrors that face each other
+const MAX_BOUNCES: usize = 8;
+/// How far a ray is moved off a portal's target face or a mirror so that it starts in the cell in front of it
+const BOUNCE_NUDGE: f64 = 1e-6;
+
+/// Colour shown wherever neither the world nor the sky is drawn
+const GREY: image::Rgba<u8> = image::Rgba([51, 51, 51, 255]);
+
+/// Everything about a wall hit that is the same for every pixel of it, looked up once before the hit is drawn
+struct HitShading<'a>{
+    /// Decals on the face of the wall that was hit
+    decals: Vec<&'a Decal>,
+    /// Light reaching the wall's face
+    light: [f64; 3],
+    mirror: Option<&'a Mirror>
+}
+
+impl<'a> HitShading<'a>{
+    fn new(hit: &ray::RayHit, world: &'a map::Map) -> Self{
+        HitShading{
+            decals: world.decals.iter().filter(|decal| decal.cell == hit.cell && decal.side == hit.side).collect(),
+            light: world.light_at(hit.pos),
+            // Only hits that bounced the ray need to look for their mirror
+            mirror: if hit.mirror { world.mirror_at(hit.cell.0, hit.cell.1) } else { None }
+        }
+    }
+}
+
+/// Software renderer that draws the world, sprites and sky into an image without needing a window,
+/// the game uploads its output to the GPU each frame
+pub struct Renderer{
+    pub texture_atlas: Vec<Texture>,
+    pub sprite_atlas: Vec<Texture>,
+    pub sky: Sky,
+    pub settings: Settings,
+    /// Draws in indexed colour when set, see `Renderer::use_palette`
+    palette: Option<Palette>,
+    /// Every texture as it was before being quantized to the palette, put back by `Renderer::clear_palette`
+    truecolour: Vec<Texture>,
+    /// Full frame effects run over every finished frame, in order
+    pub effects: Vec<Box<dyn Effect>>
+}
+
+impl Renderer {
+    /// Creates a renderer that draws in truecolor with no effects
+    pub fn new(texture_atlas: Vec<Texture>, sprite_atlas: Vec<Texture>, sky: Sky, settings: Settings) -> Self {
+        Renderer{
+            texture_atlas,
+            sprite_atlas,
+            sky,
+            settings,
+            palette: None,
+            truecolour: Vec::new(),
+            effects: Vec::new()
+        }
+    }
+
+    /// Draws a full frame of the map from the player's point of view at the frame size in the settings,
+    /// along with the depth of every pixel for effects and picking
+    pub fn render(&self, play: &player::Player, world: &map::Map, sprites: &mut [sprite::Sprite]) -> (RgbaImage, DepthBuffer) {
+        let (width, height) = (self.settings.frame_size().0 as f64, self.settings.frame_size().1 as f64);
+        // More rays than columns would never be seen, which matters when drawing at a low internal resolution
+        let ray_count = self.settings.ray_count.min(width as usize);
+        let rays = Renderer::find_ray_intersections(play, world, ray_count, self.settings.parallel);
+        let (mut img, mut depth_buffer) = self.create_texture(play, world, &rays, width, height);
+        self.draw_sprites(play, world, &rays, &mut depth_buffer, sprites, &mut img);
+        self.draw_sky(play, &rays, &mut img);
+        for effect in self.effects.iter(){
+            effect.apply(&mut img, &depth_buffer);
+        }
+        if let Some(palette) = &self.palette {
+            palette.present(&mut img, self.settings.dither);
+        }
+        else if self.settings.dither {
+            dither::dither(&mut img, 32);
+        }
+        (img, depth_buffer)
+    }
+
+    /// Adds an effect to the end of the chain run over each frame
+    pub fn add_effect(&mut self, effect: impl Effect) {
+        self.effects.push(Box::new(effect));
+    }
+
+    /// Finds the first effect of a type so it can be changed while the game runs, such as triggering a damage flash
+    pub fn effect_mut<T: Effect>(&mut self) -> Option<&mut T> {
+        self.effects.iter_mut().find_map(|effect| (effect.as_mut() as &mut dyn std::any::Any).downcast_mut::<T>())
+    }
+
+    /// Moves every effect on by a time step
+    pub fn update_effects(&mut self, dt: f64) {
+        for effect in self.effects.iter_mut(){
+            effect.update(dt);
+        }
+    }
+
+    /// Switches to indexed colour, quantizing every texture to the palette. Lighting and fog are then looked up
+    /// in the palette's colormaps, which only follow the brightness of coloured lights. The fog colormaps are built
+    /// for the given fog, see `Renderer::update_fog` for levels whose fog changes
+    pub fn use_palette(&mut self, mut palette: Palette, fog: &Fog) {
+        // Switching from one palette to another quantizes the original textures rather than the last palette's
+        self.clear_palette();
+        self.truecolour = self.textures_mut().map(|texture| texture.clone()).collect();
+        for texture in self.textures_mut(){
+            texture.quantize(&palette);
+        }
+        palette.set_fog_colour(fog.colour);
+        self.palette = Some(palette);
+    }
+
+    /// Switches back to truecolor, putting every texture back as it was before `Renderer::use_palette` quantized it
+    pub fn clear_palette(&mut self) {
+        let truecolour = std::mem::take(&mut self.truecolour);
+        for (texture, original) in self.textures_mut().zip(truecolour){
+            *texture = original;
+        }
+        self.palette = None;
+    }
+
+    /// Gets the palette when drawing in indexed colour
+    pub fn palette(&self) -> Option<&Palette> {
+        self.palette.as_ref()
+    }
+
+    /// Gets the palette to change while the game runs, such as swapping its colours for a damage flash
+    pub fn palette_mut(&mut self) -> Option<&mut Palette> {
+        self.palette.as_mut()
+    }
+
+    /// Every texture the renderer draws with, the atlases then the sky and its cloud layers
+    fn textures_mut(&mut self) -> impl Iterator<Item = &mut Texture> + '_ {
+        let sky_textures = std::iter::once(&mut self.sky.texture).chain(self.sky.clouds.iter_mut().map(|layer| &mut layer.texture));
+        self.texture_atlas.iter_mut().chain(self.sprite_atlas.iter_mut()).chain(sky_textures)
+    }
+
+    /// Rebuilds the palette's fog colormaps when the fog colour has changed, such as when a new level is loaded
+    pub fn update_fog(&mut self, fog: &Fog) {
+        if let Some(palette) = self.palette.as_mut().filter(|palette| palette.fog_colour() != fog.colour) {
+            palette.set_fog_colour(fog.colour);
+        }
+    }
+
+    /// Fills everything the world did not cover with the sky, which sits above the horizon so it moves as the player looks up and down.
+    /// Each column looks at the part of the panorama its ray points towards so the sky turns with the player,
+    /// following the ray through any portals it went into and mirrors it bounced off
+    fn draw_sky(&self, play: &player::Player, rays: &[ray::Ray], img: &mut RgbaImage) {
+        let (width, height) = (img.width() as f64, img.height() as f64);
+        let sky_top = play.horizon(height) - height;
+        for x in 0..img.width() {
+            let camera_x: f64 = 2.0 * x as f64 / width - 1.0; //x-coordinate in camera space
+            let mut dir = (play.dir.0 + play.plane.0 * camera_x, play.dir.1 + play.plane.1 * camera_x);
+            //turn the column's direction the way the last leg of its ray was turned, which keeps the sky smooth when rays cover several columns
+            if let Some(leg) = Renderer::ray_for_column(rays, x, width).and_then(|ray| ray.legs.last()) {
+                dir = leg.unfold.inverse().apply_dir(dir);
+            }
+            let sky_x = Sky::texture_pos(dir);
+            for y in 0..img.height() {
+                let pixel = *img.get_pixel(x, y);
+                if pixel[3] == 255 { continue; }
+                // The sky covers one screen height above the horizon
+                let sky_y = (y as f64 - sky_top) / height;
+                let bg = if (0.0..1.0).contains(&sky_y) { self.sky_pixel(sky_x, sky_y, height) } else { GREY };
+                img.put_pixel(x, y, Renderer::blend_pixel(pixel, bg));
+            }
+        }
+    }
+
+    /// Gets the colour of the panorama at a point with the cloud layers blended over it
+    fn sky_pixel(&self, sky_x: f64, sky_y: f64, height: f64) -> image::Rgba<u8> {
+        let mut pixel = self.sample(&self.sky.texture, sky_x, sky_y, self.sky.texture.height as f64 / height);
+        pixel[3] = 255;
+        for layer in self.sky.clouds.iter(){
+            let cloud = self.sample(&layer.texture, layer.texture_pos(sky_x), sky_y, layer.texture.height as f64 / height);
+            pixel = Renderer::blend_pixel(cloud, pixel);
+        }
+        pixel
+    }
+
+    /// Lights a pixel with the light reaching it, halving it if it is in shadow, then fades it into the fog based on how far it is from the player.
+    /// In indexed colour both steps are lookups in the palette's colormaps
+    fn shade_pixel(&self, mut pixel: image::Rgba<u8>, light: [f64; 3], dist: f64, shadow: bool, fog: &Fog) -> image::Rgba<u8> {
+        let shadow = if shadow { 0.5 } else { 1.0 };
+        if let Some(palette) = &self.palette {
+            let index = palette.light(palette.index([pixel[0], pixel[1], pixel[2]]), (light[0] + light[1] + light[2]) / 3.0 * shadow);
+            // The fog colormaps only cover one colour, until `update_fog` catches up any other fog is worked out per channel
+            // and matched back to the palette
+            let index = if fog.colour == palette.fog_colour() { palette.fog(index, fog.amount(dist)) }
+            else {
+                let colour = palette.colours[index as usize];
+                let fogged = fog.apply(image::Rgba([colour[0], colour[1], colour[2], 255]), dist);
+                palette.index([fogged[0], fogged[1], fogged[2]])
+            };
+            let colour = palette.colours[index as usize];
+            return image::Rgba([colour[0], colour[1], colour[2], pixel[3]]);
+        }
+        for i in 0..3{
+            pixel[i] = (pixel[i] as f64 * light[i] * shadow).min(255.0) as u8;
+        }
+        fog.apply(pixel, dist)
+    }
+
+    /// Blends a pixel over the one behind it using its alpha
+    fn blend_pixel(mut pixel: image::Rgba<u8>, bg: image::Rgba<u8>) -> image::Rgba<u8> {
+        let fga = pixel[3] as f64 / 255.0;
+        for i in 0..3{
+            //fg.R * fg.A / r.A + bg.R * bg.A * (1 - fg.A) / r.A;
+            pixel[i] = (pixel[i] as f64 * fga + bg[i] as f64 * 1.0 * (1.0 - fga)) as u8;
+        }
+        if bg[3] == 255 {pixel[3] = 255;}
+        pixel
+    }
+
+    /// Samples a tiling texture with the filter from the settings, `footprint` is how many texture pixels one screen pixel covers
+    fn sample(&self, tex: &Texture, u: f64, v: f64, footprint: f64) -> image::Rgba<u8> {
+        tex.sample_filtered(u, v, footprint, self.settings.filter, true)
+    }
+
+    /// Gets the lit and shaded colour of a wall at a row of the screen, repeating the texture every world unit.
+    /// Decals on the wall's face are blended over the texture first so they are lit the same way
+    fn wall_pixel(&self, hit: &ray::RayHit, y: f64, top: f64, h: f64, shading: &HitShading, fog: &Fog) -> image::Rgba<u8> {
+        let tex = &self.texture_atlas[(hit.texture_index - 1) as usize];
+        let shadow = hit.side == 0 || hit.side == 2;
+        let pixel_y = ((y - top) / h).fract();
+        // Each world unit of wall is h pixels tall, which picks the mipmap
+        let mut texel = self.sample(tex, hit.texture_pos, pixel_y, tex.height as f64 / h);
+        let z = hit.height - (y - top) / h;
+        for decal in shading.decals.iter(){
+            if let Some((u, v)) = decal.texture_pos(hit.texture_pos, z) {
+                let decal_tex = &self.sprite_atlas[decal.texture_index as usize];
+                let mut pixel = decal_tex.sample_filtered(u, v, decal_tex.height as f64 / (decal.size.1 * h), self.settings.filter, false);
+                pixel[3] = (pixel[3] as f64 * decal.opacity()) as u8;
+                texel = Renderer::blend_pixel(pixel, texel);
+            }
+        }
+        self.shade_pixel(texel, shading.light, hit.length, shadow, fog)
+    }
+
+    /// Casts the floor and ceiling for one column of the screen, texturing each pixel from the map cell it lands on.
+    /// Cells with an empty layer are left transparent so the sky shows through. Rows further away than a portal the column's ray
+    /// went through are cast from the portal's target instead
+    fn draw_floor_and_ceiling(&self, column: &mut [image::Rgba<u8>], depth: &mut [f64], play: &player::Player, world: &map::Map, camera_x: f64, ray: Option<&ray::Ray>) {
+        let (pos_x, pos_y) = play.pos;
+        let (plane_x, plane_y): (f64, f64) = play.plane;
+        let (dir_x, dir_y): (f64, f64) = play.dir;
+
+        // Direction of the column through the camera plane
+        let ray_dir_x = dir_x + plane_x * camera_x;
+        let ray_dir_y = dir_y + plane_y * camera_x;
+        let height = column.len() as f64;
+        let horizon = play.horizon(height);
+
+        for (y, pixel) in column.iter_mut().enumerate() {
+            // Distance of the row from the horizon, rows above it are ceiling and rows below it are floor
+            let p = y as f64 + 0.5 - horizon;
+            // Vertical distance from the camera to the floor or ceiling
+            let (layer, p, cam_z) = if p > 0.0 { (map::Layer::Floor, p, play.pos_z) } else { (map::Layer::Ceiling, -p, 1.0 - play.pos_z) };
+
+            // Horizontal distance from the player to the row, using the same projection as the walls
+            let row_dist = (height * cam_z) / p;
+
+            let (floor_x, floor_y) = match ray {
+                Some(ray) if ray.leg_at(row_dist).start > 0.0 => ray.point_at(row_dist),
+                _ => (pos_x + row_dist * ray_dir_x, pos_y + row_dist * ray_dir_y)
+            };
+            let ti = world.texture(world.get(layer, floor_x.floor() as i32, floor_y.floor() as i32));
+
+            if ti > 0 {
+                let tex = &self.texture_atlas[(ti - 1) as usize];
+                let texel = self.sample(tex, floor_x - floor_x.floor(), floor_y - floor_y.floor(), tex.height as f64 * row_dist / height);
+                *pixel = self.shade_pixel(texel, world.light_at((floor_x, floor_y)), row_dist, false, &world.fog);
+                depth[y] = row_dist;
+            }
+        }
+    }
+
+    /// Draws the top face of a wall that is lower than the camera by casting each row onto the top of the cell
+    fn draw_wall_top(&self, column: &mut [image::Rgba<u8>], depth: &mut [f64], play: &player::Player, ray: &ray::Ray, hit: &ray::RayHit, world: &map::Map) {
+        let tex = &self.texture_atlas[(hit.texture_index - 1) as usize];
+        let height = column.len() as f64;
+        let horizon = play.horizon(height);
+        let cam_z = play.pos_z - hit.height;
+        let near = horizon + cam_z * height / hit.length;
+        let far = horizon + cam_z * height / hit.exit_length;
+        // Close to the camera the far edge can project below the bottom of the frame
+        let start = (far.max(0.0) as usize).min(column.len());
+        let end = (near.min(height) as usize).max(start);
+        for (y, bg) in column[start..end].iter_mut().enumerate() {
+            let y = y + start;
+            // Horizontal distance from the player to where the row meets the top of the wall
+            let row_dist = cam_z * height / (y as f64 + 0.5 - horizon);
+            let (top_x, top_y) = ray.point_at(row_dist);
+            let texel = self.sample(tex, top_x - top_x.floor(), top_y - top_y.floor(), tex.height as f64 * row_dist / height);
+            let pixel = self.shade_pixel(texel, world.light_at((top_x, top_y)), row_dist, false, &world.fog);
+            if hit.transparent {
+                *bg = Renderer::blend_pixel(pixel, *bg);
+            }
+            else {
+                *bg = pixel;
+                depth[y] = row_dist;
+            }
+        }
+    }
+
+    /// Draws the walls along a ray into one column of the screen, solid walls record their distance in the column's depth.
+    /// See-through walls and mirrors leave the depth alone so sprites behind them, or seen in them, still show
+    fn draw_walls(&self, column: &mut [image::Rgba<u8>], depth: &mut [f64], play: &player::Player, ray: &ray::Ray, world: &map::Map) {
+        let height = column.len() as f64;
+
+        // Draw the walls from furthest to nearest so that closer walls cover (or blend over) the ones behind them
+        for hit in ray.hits.iter().rev(){
+            // Calculate the height of one world unit at this distance and where the wall sits relative to the camera height
+            let (top, bottom, h) = hit.projection(play, height);
+            let shading = HitShading::new(hit, world);
+
+            // Walls lower than the camera show their top face between the near and far edge of the cell
+            if hit.height < play.pos_z {
+                self.draw_wall_top(column, depth, play, ray, hit, world);
+            }
+            
+            // Wall drawing loop
+            // Low walls close to the camera can have their top below the bottom of the frame
+            let start = (top.max(0.0) as usize).min(column.len());
+            let end = (bottom.min(height) as usize).max(start);
+            for (y, bg) in column[start..end].iter_mut().enumerate(){
+                // Get the correct pixel colour and shade it based off the view distance
+                let pixel = self.wall_pixel(hit, (y + start) as f64, top, h, &shading, &world.fog);
+                if hit.transparent {
+                    *bg = Renderer::blend_pixel(pixel, *bg);
+                }
+                else if let Some(mirror) = shading.mirror {
+                    *bg = mirror.reflect(pixel, *bg);
+                }
+                else {
+                    *bg = pixel;
+                    depth[y + start] = hit.length;
+                }
+            }
+        }
+    }
+
+    /// Gets the ray drawn in a column of the screen, each ray covers `width / rays.len()` columns
+    /// (for best results, ensure that the raycount is a factor of the screen width)
+    fn ray_for_column(rays: &[ray::Ray], x: u32, width: f64) -> Option<&ray::Ray> {
+        let ray_width = width / rays.len() as f64;
+        rays.get(((x as f64 + 0.5) / ray_width) as usize)
+    }
+
+    /// Uses the length of the provided rays to draw the world as a series of textured columns over a cast floor and ceiling,
+    /// recording the depth of every pixel drawn. Every column is independent, so they can be drawn across all cores when the settings allow it
+    pub fn create_texture(&self, play: &player::Player, world: &map::Map, rays: &[ray::Ray], width: f64, height: f64) -> (RgbaImage, DepthBuffer){
+        let mut img: RgbaImage = ImageBuffer::new(width as u32, height as u32);
+
+        let draw_column = |x: u32| {
+            let mut column = vec![image::Rgba([0, 0, 0, 0]); height as usize];
+            let mut depth = vec![f64::INFINITY; height as usize];
+            let camera_x: f64 = 2.0 * x as f64 / width - 1.0; //x-coordinate in camera space
+            let ray = Renderer::ray_for_column(rays, x, width);
+            self.draw_floor_and_ceiling(&mut column, &mut depth, play, world, camera_x, ray);
+            if let Some(ray) = ray {
+                self.draw_walls(&mut column, &mut depth, play, ray, world);
+            }
+            (column, depth)
+        };
+        let (columns, depths): (Vec<Vec<image::Rgba<u8>>>, Vec<Vec<f64>>) = if self.settings.parallel {
+            (0..width as u32).into_par_iter().map(draw_column).unzip()
+        }
+        else {
+            (0..width as u32).map(draw_column).unzip()
+        };
+
+        for (x, column) in columns.iter().enumerate(){
+            for (y, pixel) in column.iter().enumerate(){
+                img.put_pixel(x as u32, y as u32, *pixel);
+            }
+        }
+        (img, DepthBuffer::from_columns(height as u32, depths))
+    }
+
+    /// Blends any see-through walls and mirrors closer than the given depth over a pixel of the ray's column, furthest first
+    #[allow(clippy::too_many_arguments)]
+    fn composite_transparent(&self, mut pixel: image::Rgba<u8>, ray: &ray::Ray, depth: f64, y: i32, play: &player::Player, screen_height: f64, world: &map::Map) -> image::Rgba<u8> {
+        for hit in ray.hits.iter().rev().filter(|hit| (hit.transparent || hit.mirror) && hit.length < depth){
+            let (top, bottom, h) = hit.projection(play, screen_height);
+            if (y as f64) >= top && (y as f64) < bottom {
+                let shading = HitShading::new(hit, world);
+                let fg = self.wall_pixel(hit, y as f64, top, h, &shading, &world.fog);
+                pixel = match shading.mirror {
+                    Some(mirror) => mirror.reflect(fg, pixel),
+                    None => Renderer::blend_pixel(fg, pixel)
+                };
+            }
+        }
+        pixel
+    }
+
+    /// Method for overlaying the games sprites over the pre-drawn environment, lit by the light where each sprite stands and faded into the level's fog.
+    /// Each pixel is tested against the depth buffer, which the sprite's own depth is written into. Sprites seen through portals
+    /// or in mirrors are drawn where the rays' unfolds put them, in the columns whose rays went the same way
+    pub fn draw_sprites(&self, play: &player::Player, world: &map::Map, rays: &[ray::Ray], depth_buffer: &mut DepthBuffer, sprites: &mut [sprite::Sprite], tex: &mut image::RgbaImage) {
+        // Update distances from player
+        for sprite in sprites.iter_mut(){
+            sprite.dist = sprite.eucl_dist(play.pos);
+        }
+        // Sort the sprites by their distance from the player
+        sprites.sort_by(|a, b| b.dist.partial_cmp(&a.dist).unwrap());
+
+        let (pos_x, pos_y) = play.pos;
+        let (plane_x, plane_y): (f64, f64) = play.plane;
+        let (dir_x, dir_y): (f64, f64) = play.dir;
+        let (screen_width, screen_height) = (tex.width() as i32, tex.height() as i32);
+        let horizon = play.horizon(screen_height as f64);
+
+        //every different way the rays were unfolded by portals and mirrors gives another place each sprite can be seen
+        let mut unfolds = vec![ray::Unfold::IDENTITY];
+        for leg in rays.iter().flat_map(|ray| ray.legs.iter()){
+            if !unfolds.contains(&leg.unfold) { unfolds.push(leg.unfold); }
+        }
+        let mut views: Vec<(&sprite::Sprite, ray::Unfold, (f64, f64))> = sprites.iter()
+            .flat_map(|sprite| unfolds.iter().map(move |unfold| (sprite, *unfold, unfold.apply(sprite.pos))))
+            .collect();
+        let dist = |pos: (f64, f64)| (pos.0 - pos_x).powi(2) + (pos.1 - pos_y).powi(2);
+        views.sort_by(|a, b| dist(b.2).partial_cmp(&dist(a.2)).unwrap());
+
+        // Draw the sprites
+        for (sprite, unfold, pos) in views{
+            let light = world.light_at(sprite.pos);
+            //directional sprites show the rotation facing the player, as seen from where the player appears to be in the sprite's own space
+            let rotation = sprite.rotation_for(unfold.inverse().apply(play.pos));
+            let sprite_tex = &self.sprite_atlas[rotation.texture_index as usize];
+
+            //translate sprite position to relative to camera
+            let sprite_x: f64 = pos.0 - pos_x;
+            let sprite_y: f64 = pos.1 - pos_y;
+
+            let inv_det: f64 = 1.0 / (plane_x * dir_y - dir_x * plane_y); //required for correct matrix multiplication
+
+            let transform_x: f64 = inv_det * (dir_y * sprite_x - dir_x * sprite_y);
+            let transform_y: f64 = inv_det * (-plane_y * sprite_x + plane_x * sprite_y); //this is actually the depth inside the screen, that what Z is in 3D
+
+            //sprites on or behind the camera plane can't be seen, and would project to nonsense screen positions
+            if transform_y <= f64::EPSILON { continue; }
+
+            let sprite_screen_x: f64 = (screen_width as f64 / 2.0) * (1.0 + transform_x / transform_y);
+
+            //calculate height of the sprite on screen
+            let unit = screen_height as f64 / transform_y; //using 'transformY' instead of the real distance prevents fisheye
+            let sprite_height = unit * sprite.scale.1;
+            //calculate lowest and highest pixel to fill in current stripe, the sprite sits at its height in the world so it moves with the camera height.
+            //everything stays in f64 until it is clamped to the frame, as sprites close to the camera can land far off screen
+            let sprite_top = horizon - (sprite.base_height() + sprite.scale.1 - play.pos_z) * unit;
+            let draw_start_y = sprite_top.clamp(0.0, screen_height as f64) as i32;
+            let draw_end_y = (sprite_top + sprite_height).clamp(0.0, screen_height as f64) as i32;
+
+            //calculate width of the sprite
+            let sprite_width = unit * sprite.scale.0;
+            let sprite_left = sprite_screen_x - sprite_width / 2.0;
+            let draw_start_x = sprite_left.clamp(0.0, screen_width as f64) as i32;
+            let draw_end_x = (sprite_left + sprite_width).clamp(0.0, screen_width as f64) as i32;
+
+            //loop through every vertical stripe of the sprite on screen
+            for stripe in draw_start_x..draw_end_x
+            {
+                let mut tex_x = (stripe as f64 - sprite_left) / sprite_width;
+                //mirrored rotations and reflections both flip the sprite, together they cancel out
+                if unfold.is_mirrored() != rotation.mirrored { tex_x = 1.0 - tex_x; }
+                let column = Renderer::ray_for_column(rays, stripe as u32, screen_width as f64);
+                //the sprite only shows in columns whose ray reached it through the same portals and mirrors
+                if column.is_some_and(|ray| ray.leg_at(transform_y).unfold != unfold) { continue; }
+                for y in draw_start_y..draw_end_y //for every pixel of the current stripe
+                {
+                    //ZBuffer, with perpendicular distance, checked per pixel as low walls only cover part of the stripe
+                    if transform_y >= depth_buffer.get(stripe as u32, y as u32) { continue; }
+                    let tex_y = (y as f64 - sprite_top) / sprite_height;
+                    if (0.0..1.0).contains(&tex_x) && (0.0..1.0).contains(&tex_y){
+                        let mut pixel = sprite_tex.sample_filtered(tex_x, tex_y, sprite_tex.height as f64 / sprite_height, self.settings.filter, false);
+                        if pixel != image::Rgba([0,0,0,0]) { 
+                            pixel = self.shade_pixel(pixel, light, transform_y, false, &world.fog);
+                            if pixel[3] < 255 {
+                                pixel = Renderer::blend_pixel(pixel, *tex.get_pixel(stripe as u32, y as u32));
+                            }
+                            //see-through walls in front of the sprite are drawn back over it
+                            if let Some(column) = column {
+                                pixel = self.composite_transparent(pixel, column, transform_y, y, play, screen_height as f64, world);
+                            }
+                            tex.put_pixel(stripe as u32, y as u32, pixel); 
+                            depth_buffer.set(stripe as u32, y as u32, transform_y);
+                        }
+                    }
+                    
+                }
+                
+            }
+        } 
+    }
+
+    /// Gets the horizontal texture co-ordinate (0.0 to 1.0) for the point along a wall that was hit
+    fn texture_pos(wall_x: f64, side: i32, ray_dir: (f64, f64)) -> f64{
+        let mut tex_x = wall_x;
+        if side == 0 || side == 1 && ray_dir.0 > 0.0 { tex_x = 1.0 - tex_x; }
+        if side == 2 || side == 3 && ray_dir.1 < 0.0 { tex_x = 1.0 - tex_x; }
+        tex_x
+    }
+
+    /// Calculates the ray intersections for the player's view, these also act as the Z-Buffer for the sprites.
+    /// Each ray is independent, so they can be cast across all cores when `parallel` is set
+    pub fn find_ray_intersections(play: &player::Player, map: &map::Map, rc: usize, parallel: bool) -> Vec<ray::Ray>{
+        let max_height = map.max_height();
+        let cast = |r: usize| {
+            let camera_x: f64 = 2.0 * r as f64 / rc as f64 - 1.0; //x-coordinate in camera space
+            Renderer::cast_ray(play, map, camera_x, max_height)
+        };
+        if parallel {
+            (0..rc).into_par_iter().map(cast).collect()
+        }
+        else {
+            (0..rc).map(cast).collect()
+        }
+    }
+
+    /// Tests a ray against the line segment walls inside a cell, recording them nearest first.
+    /// Returns true once a segment hides everything behind it
+    fn cast_segments(map: &map::Map, cell: (i32, i32), leg: ray::Leg, ray: &mut ray::Ray, max_height: f64) -> bool{
+        //segments crossing into other cells are only hit here where the crossing is inside this cell, so each is recorded once
+        let inside = |t: f64| {
+            let (x, y) = (leg.origin.0 + t * leg.dir.0, leg.origin.1 + t * leg.dir.1);
+            (x.floor() as i32, y.floor() as i32) == cell
+        };
+        let mut crossings: Vec<(f64, f64, &Segment)> = map.segments_in(cell.0, cell.1)
+            .filter_map(|segment| segment.intersect(leg.origin, leg.dir).map(|(t, s)| (t, s, segment)))
+            .filter(|&(t, _, _)| inside(t))
+            .collect();
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let height = map.get_height(cell.0, cell.1);
+        let transparent = map.is_transparent(cell.0, cell.1);
+        for (t, s, segment) in crossings{
+            //the texture repeats every world unit along the segment, flipped so it reads the same way from both sides
+            let mut along = s * segment.length();
+            if segment.is_back_facing(leg.dir) { along = segment.length() - along; }
+
+            //segments running mostly along y are shaded like the x sides of the grid
+            let (ex, ey) = (segment.end.0 - segment.start.0, segment.end.1 - segment.start.1);
+            let side = if ey.abs() > ex.abs() { if leg.dir.0 < 0.0 { 0 } else { 1 } }
+            else if leg.dir.1 < 0.0 { 2 } else { 3 };
+
+            ray.hits.push(ray::RayHit{
+                length: leg.start + t,
+                exit_length: leg.start + t,
+                texture_index: map.texture(segment.texture_index),
+                texture_pos: along - along.floor(),
+                side,
+                height,
+                cell,
+                pos: (leg.origin.0 + (t - 0.001) * leg.dir.0, leg.origin.1 + (t - 0.001) * leg.dir.1),
+                transparent,
+                mirror: false
+            });
+            let covered = map.get(map::Layer::Ceiling, cell.0, cell.1) > 0;
+            if !transparent && (height >= max_height || (height >= 1.0 && covered)) { return true; }
+        }
+        false
+    }
+
+    /// Follows a single ray through the map, recording every wall it passes through. A `camera_x` of 0.0 follows
+    /// the centre of the screen, which finds what the player is aiming at. Rays that hit a portal carry on from
+    /// its target and rays that hit a mirror bounce off it, up to `MAX_BOUNCES` times
+    pub fn cast_ray(play: &player::Player, map: &map::Map, camera_x: f64, max_height: f64) -> ray::Ray{
+        let (plane_x, plane_y): (f64, f64) = play.plane;
+        let (dir_x, dir_y): (f64, f64) = play.dir;
+
+        //calculate ray position and direction
+        let ray_dir_x = dir_x + plane_x * camera_x;
+        let ray_dir_y = dir_y + plane_y * camera_x;
+        let first = ray::Leg{
+            start: 0.0,
+            origin: play.pos,
+            dir: (ray_dir_x, ray_dir_y),
+            unfold: ray::Unfold::IDENTITY
+        };
+        let mut ray = ray::Ray{
+            dir: first.dir,
+            hits: Vec::new(),
+            legs: vec![first]
+        };
+
+        let mut leg = first;
+        for bounces in 0..=MAX_BOUNCES{
+            match Renderer::cast_leg(map, leg, &mut ray, max_height, bounces < MAX_BOUNCES){
+                Some(next) => {
+                    ray.legs.push(next);
+                    leg = next;
+                }
+                None => break
+            }
+        }
+        ray
+    }
+
+    /// Runs the DDA along one straight leg of a ray. Returns the next leg if the ray went into a portal or bounced off a mirror,
+    /// once `bounce` is false both are treated as plain walls
+    fn cast_leg(map: &map::Map, leg: ray::Leg, ray: &mut ray::Ray, max_height: f64, bounce: bool) -> Option<ray::Leg>{
+        let (pos_x, pos_y) = leg.origin;
+        let (ray_dir_x, ray_dir_y) = leg.dir;
+        let (mut map_x, mut map_y): (i32, i32) = (pos_x.floor() as i32, pos_y.floor() as i32);
+        let (mut side_dist_x, mut side_dist_y): (f64, f64);
+        //length of ray from one x or y-side to next x or y-side
+        let (delta_dist_x, delta_dist_y): (f64, f64);
+        if ray_dir_x == 0.0 { delta_dist_x = 10000.0; } else { delta_dist_x = (1.0 / ray_dir_x).abs(); }
+        if ray_dir_y == 0.0 { delta_dist_y = 10000.0; } else { delta_dist_y = (1.0 / ray_dir_y).abs(); }
+
+        //what direction to step in x or y-direction (either +1 or -1)
+        let step_x: i32;
+        let step_y: i32;
+
+        let mut side: i32; //was a NS or a EW wall hit?
+
+        //calculate step and initial sideDist
+        if ray_dir_x < 0.0
+        {
+            step_x = -1;
+            side_dist_x = (pos_x - map_x as f64) * delta_dist_x;
+        }
+        else
+        {
+            step_x = 1;
+            side_dist_x = (map_x as f64 + 1.0 - pos_x) * delta_dist_x;
+        }
+        if ray_dir_y < 0.0
+        {
+            step_y = -1;
+            side_dist_y = (pos_y - map_y as f64) * delta_dist_y;
+        }
+        else
+        {
+            step_y = 1;
+            side_dist_y = (map_y as f64 + 1.0 - pos_y) * delta_dist_y;
+        }
+
+        //the starting cell can hold line segment walls in front of the ray
+        if Renderer::cast_segments(map, (map_x, map_y), leg, ray, max_height) { return None; }
+
+        //perform DDA until the ray hits a wall tall enough to hide everything behind it, or leaves the map
+        loop
+        {
+            //jump to next map square, either in x-direction, or in y-direction
+            let (prev_x, prev_y) = (map_x, map_y);
+            if side_dist_x < side_dist_y
+            {
+                side_dist_x += delta_dist_x;
+                map_x += step_x;
+                if ray_dir_x < 0.0{
+                    side = 0;
+                }
+                else {
+                    side = 1;
+                }
+            }
+            else
+            {
+                side_dist_y += delta_dist_y;
+                //println!("{0} + {1}", map_y, step_y);
+                map_y += step_y;
+                if ray_dir_y < 0.0{
+                    side = 2;
+                }
+                else {
+                    side = 3;
+                }
+            }
+            //Check if ray has hit a wall
+            let ti: u8 = map.get(map::Layer::Wall, map_x, map_y);
+            if ti > 0 {
+                //Calculate distance projected on camera direction (Euclidean distance would give fisheye effect!)
+                let perp_wall_dist: f64 = if side == 0 || side == 1 { side_dist_x - delta_dist_x }
+                else { side_dist_y - delta_dist_y };
+
+                //portals send the ray on from their target face, nudged off it so it starts in the cell in front of the target
+                if let Some(portal) = map.portal_at(map_x, map_y, Face::from_side(side)).filter(|_| bounce) {
+                    let dir = portal.transform_dir(leg.dir);
+                    let exit = portal.transform_point((pos_x + perp_wall_dist * ray_dir_x, pos_y + perp_wall_dist * ray_dir_y));
+                    return Some(ray::Leg{
+                        start: leg.start + perp_wall_dist + BOUNCE_NUDGE,
+                        origin: (exit.0 + dir.0 * BOUNCE_NUDGE, exit.1 + dir.1 * BOUNCE_NUDGE),
+                        dir,
+                        unfold: leg.unfold.then(&portal.transform().inverse())
+                    });
+                }
+
+                //texturing calculations
+                //calculate value of wallX
+                let mut wall_x: f64; //where exactly the wall was hit
+                if side == 0 || side == 1 { wall_x = pos_y + perp_wall_dist * ray_dir_y; }
+                else { wall_x = pos_x + perp_wall_dist * ray_dir_x; }
+                wall_x -= wall_x.floor();
+
+                //light the wall from just in front of it, inside the cell the ray came from
+                let hit_pos = (pos_x + (perp_wall_dist - 0.001) * ray_dir_x, pos_y + (perp_wall_dist - 0.001) * ray_dir_y);
+
+                //the walls either side of a door show the door frame instead of their own texture
+                let texture_index = match map.door_at(prev_x, prev_y) {
+                    Some(door) if door.vertical == (side == 2 || side == 3) => map.texture(door.frame_texture),
+                    _ => map.texture(ti)
+                };
+
+                let height = map.get_height(map_x, map_y);
+                let transparent = map.is_transparent(map_x, map_y);
+                let mirror = bounce && map.mirror_at(map_x, map_y).is_some();
+                ray.hits.push(ray::RayHit{
+                    length: leg.start + perp_wall_dist,
+                    exit_length: leg.start + side_dist_x.min(side_dist_y),
+                    texture_index,
+                    texture_pos: Renderer::texture_pos(wall_x, side, leg.dir),
+                    side,
+                    height,
+                    cell: (map_x, map_y),
+                    pos: hit_pos,
+                    transparent,
+                    mirror
+                });
+                //mirrors send the ray back off the face they were hit on, what it sees is reflected across the face
+                if mirror {
+                    let (dir, reflect) = match side{
+                        0 => ((-ray_dir_x, ray_dir_y), ray::Unfold::mirror_x(map_x as f64 + 1.0)),
+                        1 => ((-ray_dir_x, ray_dir_y), ray::Unfold::mirror_x(map_x as f64)),
+                        2 => ((ray_dir_x, -ray_dir_y), ray::Unfold::mirror_y(map_y as f64 + 1.0)),
+                        _ => ((ray_dir_x, -ray_dir_y), ray::Unfold::mirror_y(map_y as f64))
+                    };
+                    let hit = (pos_x + perp_wall_dist * ray_dir_x, pos_y + perp_wall_dist * ray_dir_y);
+                    return Some(ray::Leg{
+                        start: leg.start + perp_wall_dist + BOUNCE_NUDGE,
+                        origin: (hit.0 + dir.0 * BOUNCE_NUDGE, hit.1 + dir.1 * BOUNCE_NUDGE),
+                        dir,
+                        unfold: leg.unfold.then(&reflect)
+                    });
+                }
+                //a wall that reaches the ceiling also hides everything behind it unless the ray came from under open sky
+                let covered = map.get(map::Layer::Ceiling, prev_x, prev_y) > 0;
+                if !transparent && (height >= max_height || (height >= 1.0 && covered)) { return None; }
+            }
+            else if Renderer::cast_segments(map, (map_x, map_y), leg, ray, max_height) {
+                return None;
+            }
+            else if let Some(door) = map.door_at(map_x, map_y) {
+                //doors sit halfway across their cell, so find where the ray crosses that line
+                let (door_dist, door_side) = if door.vertical {
+                    ((map_x as f64 + 0.5 - pos_x) / ray_dir_x, if ray_dir_x < 0.0 { 0 } else { 1 })
+                }
+                else {
+                    ((map_y as f64 + 0.5 - pos_y) / ray_dir_y, if ray_dir_y < 0.0 { 2 } else { 3 })
+                };
+                let mut wall_x: f64 = if door.vertical { pos_y + door_dist * ray_dir_y } else { pos_x + door_dist * ray_dir_x };
+                wall_x -= wall_x.floor();
+
+                //the ray only hits the door if it crosses the middle of the cell before leaving it, and misses the part that has slid open
+                let exit_dist = side_dist_x.min(side_dist_y);
+                if door_dist > 0.0 && door_dist < exit_dist && wall_x >= door.offset {
+                    ray.hits.push(ray::RayHit{
+                        length: leg.start + door_dist,
+                        exit_length: leg.start + door_dist,
+                        texture_index: map.texture(door.texture_index),
+                        texture_pos: Renderer::texture_pos(wall_x - door.offset, door_side, leg.dir),
+                        side: door_side,
+                        height: 1.0,
+                        cell: (map_x, map_y),
+                        pos: (pos_x + (door_dist - 0.001) * ray_dir_x, pos_y + (door_dist - 0.001) * ray_dir_y),
+                        transparent: false,
+                        mirror: false
+                    });
+                    let covered = map.get(map::Layer::Ceiling, map_x, map_y) > 0;
+                    if 1.0 >= max_height || covered { return None; }
+                }
+            }
+            else if map_x < 0 || map_x >= map.map_dim.0 || map_y < 0 || map_y >= map.map_dim.1 {
+                return None;
+            }
+        }
+    }
+}
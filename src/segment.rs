@@ -0,0 +1,66 @@
+/// A straight wall between two points, used for diagonal walls and pillars. It can cross from one map cell into the next
+/// and takes the height and transparency of whichever cell each part of it is in
+pub struct Segment{
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+    pub texture_index: u8
+}
+
+impl Segment{
+    pub fn new(start: (f64, f64), end: (f64, f64), texture_index: u8) -> Self{
+        Segment{
+            start,
+            end,
+            texture_index
+        }
+    }
+
+    pub fn length(&self) -> f64{
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Checks whether any part of the segment lies inside a map cell, edges included
+    pub fn touches_cell(&self, cell: (i32, i32)) -> bool{
+        // Clip the segment to the cell one axis at a time, keeping the part from t0 to t1 along it
+        let (mut t0, mut t1) = (0.0, 1.0);
+        for (start, delta, min) in [(self.start.0, self.end.0 - self.start.0, cell.0 as f64), (self.start.1, self.end.1 - self.start.1, cell.1 as f64)]{
+            if delta == 0.0 {
+                if start < min || start > min + 1.0 { return false; }
+                continue;
+            }
+            let (a, b) = ((min - start) / delta, (min + 1.0 - start) / delta);
+            t0 = a.min(b).max(t0);
+            t1 = a.max(b).min(t1);
+        }
+        t0 <= t1
+    }
+
+    /// Finds where a ray crosses the segment, returning how far along the ray (in multiples of `dir`)
+    /// and how far along the segment (0.0 at the start to 1.0 at the end) the crossing is
+    pub fn intersect(&self, origin: (f64, f64), dir: (f64, f64)) -> Option<(f64, f64)>{
+        let (ex, ey) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let denom = dir.0 * ey - dir.1 * ex;
+        if denom == 0.0 { return None; }
+        let (ox, oy) = (self.start.0 - origin.0, self.start.1 - origin.1);
+        let t = (ox * ey - oy * ex) / denom;
+        let s = (ox * dir.1 - oy * dir.0) / denom;
+        if t > 0.0 && (0.0..=1.0).contains(&s) { Some((t, s)) } else { None }
+    }
+
+    /// Checks whether a ray hits the segment from its back, so the texture can be flipped to read the same way from both sides
+    pub fn is_back_facing(&self, dir: (f64, f64)) -> bool{
+        let (ex, ey) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        dir.0 * ey - dir.1 * ex > 0.0
+    }
+
+    /// Gets the shortest distance from a point to the segment
+    pub fn distance_to(&self, point: (f64, f64)) -> f64{
+        let (ex, ey) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let (px, py) = (point.0 - self.start.0, point.1 - self.start.1);
+        let len_sq = ex * ex + ey * ey;
+        let s = if len_sq == 0.0 { 0.0 } else { ((px * ex + py * ey) / len_sq).clamp(0.0, 1.0) };
+        let (dx, dy) = (px - s * ex, py - s * ey);
+        (dx * dx + dy * dy).sqrt()
+    }
+}
@@ -0,0 +1,54 @@
+use crate::global;
+use crate::texture::Filter;
+
+/// Render options that can be changed while the game is running
+pub struct Settings{
+    /// Size of the window in pixels, which the frame is drawn at unless `internal_resolution` is set
+    pub resolution: (u32, u32),
+    /// Fixed size to draw the frame at, such as 320x200, which is then scaled up to the window without smoothing
+    pub internal_resolution: Option<(u32, u32)>,
+    /// Keeps the shape of the frame when scaling it up, using the largest whole pixel multiple that fits and leaving bars
+    /// around the edge, rather than stretching it over the window
+    pub letterbox: bool,
+    /// Smooths the steps in the shading with ordered dithering, in 15-bit colour or to the palette in indexed colour
+    pub dither: bool,
+    /// Number of rays cast across the screen, each is drawn as a column `resolution.0 / ray_count` pixels wide
+    pub ray_count: usize,
+    /// Casts rays and draws columns across all CPU cores, turn off to compare against the single threaded path
+    pub parallel: bool,
+    /// How wall, floor and sprite textures are sampled
+    pub filter: Filter
+}
+
+impl Settings{
+    /// Size the frame is drawn at
+    pub fn frame_size(&self) -> (u32, u32){
+        self.internal_resolution.unwrap_or(self.resolution)
+    }
+
+    /// Gets the rectangle of the window the frame is drawn over as x, y, width and height
+    pub fn viewport(&self) -> [f64; 4]{
+        let (window_w, window_h) = (self.resolution.0 as f64, self.resolution.1 as f64);
+        let (frame_w, frame_h) = (self.frame_size().0 as f64, self.frame_size().1 as f64);
+        if !self.letterbox { return [0.0, 0.0, window_w, window_h]; }
+        // Whole multiples keep every pixel the same size, a window smaller than the frame can only shrink it
+        let fit = (window_w / frame_w).min(window_h / frame_h);
+        let scale = if fit < 1.0 { fit } else { fit.floor() };
+        let (w, h) = (frame_w * scale, frame_h * scale);
+        [((window_w - w) / 2.0).floor(), ((window_h - h) / 2.0).floor(), w, h]
+    }
+}
+
+impl Default for Settings{
+    fn default() -> Self{
+        Settings{
+            resolution: (global::X as u32, global::Y as u32),
+            internal_resolution: None,
+            letterbox: false,
+            dither: false,
+            ray_count: global::X as usize,
+            parallel: true,
+            filter: Filter::Nearest
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::texture::Texture;
+
+/// A see-through layer of clouds drawn over the sky that drifts on its own
+pub struct CloudLayer{
+    pub texture: Texture,
+    /// How many texture widths the clouds drift each second
+    pub speed: f64,
+    /// How many times the texture wraps around the full circle, higher values feel closer as they sweep past faster when turning
+    pub repeat: f64,
+    /// How far the clouds have drifted, from 0.0 to 1.0 of the texture width
+    pub offset: f64
+}
+
+impl CloudLayer{
+    pub fn new(texture: Texture, speed: f64, repeat: f64) -> Self{
+        CloudLayer{
+            texture,
+            speed,
+            repeat,
+            offset: 0.0
+        }
+    }
+
+    /// Gets the horizontal texture co-ordinate for a direction around the sky
+    pub fn texture_pos(&self, sky_u: f64) -> f64{
+        (sky_u * self.repeat + self.offset).rem_euclid(1.0)
+    }
+}
+
+/// A 360° panoramic sky, the width of the texture wraps once around the player
+pub struct Sky{
+    pub texture: Texture,
+    /// Cloud layers drawn over the panorama, first to last
+    pub clouds: Vec<CloudLayer>
+}
+
+impl Sky{
+    pub fn new(texture: Texture) -> Self{
+        Sky{
+            texture,
+            clouds: Vec::new()
+        }
+    }
+
+    /// Drifts every cloud layer along by its speed
+    pub fn update(&mut self, dt: f64){
+        for layer in self.clouds.iter_mut(){
+            layer.offset = (layer.offset + layer.speed * dt).rem_euclid(1.0);
+        }
+    }
+
+    /// Gets the horizontal position on the panorama (0.0 to 1.0) that a direction in the world looks at,
+    /// the angle is negated as the map's y axis points down so the texture reads left to right on screen
+    pub fn texture_pos(dir: (f64, f64)) -> f64{
+        (-dir.1).atan2(dir.0).rem_euclid(std::f64::consts::TAU) / std::f64::consts::TAU
+    }
+}
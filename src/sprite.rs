@@ -1,14 +1,172 @@
+use std::f64::consts::TAU;
+
+use crate::animation::{Animation, Clip};
+
+/// Numbers of evenly spaced angles a directional sprite can be drawn from
+pub const SUPPORTED_ROTATIONS: [usize; 3] = [1, 8, 16];
+
+/// Picks which of `count` evenly spaced rotations is closest to an angle measured from the front
+fn rotation_index(count: usize, view_angle: f64) -> usize{
+    let step = TAU / count as f64;
+    (view_angle.rem_euclid(TAU) / step).round() as usize % count
+}
+
+/// How a sprite looks from one angle
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rotation{
+    /// Sprite atlas entry to draw
+    pub texture_index: u8,
+    /// Draws the entry flipped left to right, so one image can show both sides of a symmetric sprite
+    pub mirrored: bool
+}
+
+impl Rotation{
+    pub fn new(texture_index: u8, mirrored: bool) -> Self{
+        Rotation{
+            texture_index,
+            mirrored
+        }
+    }
+}
+
+/// Describes how a directional sprite looks from evenly spaced angles around it, so level data can define one.
+/// The first rotation is seen from the front and the rest go round in the same direction as `Sprite::facing`,
+/// so 8 rotations give a view every 45°
+#[derive(Clone)]
+pub struct SpriteDef{
+    rotations: Vec<Rotation>
+}
+
+impl SpriteDef{
+    /// Creates a definition from its rotations, None unless there are 1, 8 or 16 of them
+    pub fn new(rotations: Vec<Rotation>) -> Option<Self>{
+        if !SUPPORTED_ROTATIONS.contains(&rotations.len()) { return None; }
+        Some(SpriteDef{ rotations })
+    }
+
+    /// Creates a definition with a separate atlas entry for every rotation, None unless there are 1, 8 or 16 of them
+    pub fn from_textures(textures: &[u8]) -> Option<Self>{
+        SpriteDef::new(textures.iter().map(|&texture_index| Rotation::new(texture_index, false)).collect())
+    }
+
+    /// Creates 8 rotations for a sprite whose left and right sides are mirror images, from 5 atlas entries
+    /// going round from the front to the back. The other 3 rotations reuse those entries flipped
+    pub fn symmetric(textures: [u8; 5]) -> Self{
+        let mut rotations: Vec<Rotation> = textures.iter().map(|&texture_index| Rotation::new(texture_index, false)).collect();
+        rotations.extend(textures[1..4].iter().rev().map(|&texture_index| Rotation::new(texture_index, true)));
+        SpriteDef{ rotations }
+    }
+
+    pub fn rotations(&self) -> &[Rotation]{
+        &self.rotations
+    }
+
+    /// Gets the angle from the front that a rotation is seen from, in radians
+    pub fn angle(&self, index: usize) -> f64{
+        TAU * index as f64 / self.rotations.len() as f64
+    }
+
+    /// Gets the rotation closest to an angle measured from the front of the sprite
+    pub fn rotation_at(&self, view_angle: f64) -> Rotation{
+        self.rotations[rotation_index(self.rotations.len(), view_angle)]
+    }
+}
+
+/// Where a sprite sits between the floor and the ceiling
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Anchor{
+    /// Stands on the floor
+    Floor,
+    /// Floats halfway between the floor and ceiling
+    Centre,
+    /// Hangs from the ceiling
+    Ceiling
+}
+
 pub struct Sprite{
     pub pos: (f64, f64),
     pub texture_index: u8,
-    pub dist: f64
+    pub dist: f64,
+    /// Direction the sprite faces as an angle in radians, measured the same way as `atan2` on map co-ordinates
+    pub facing: f64,
+    /// How the sprite looks from each angle around it, None for a sprite that looks the same from every side and uses `texture_index`
+    pub def: Option<SpriteDef>,
+    /// Clip being played, its current frame is drawn in place of `rotations`
+    pub animation: Option<Animation>,
+    /// Width and height of the sprite in world units, where 1.0 is as tall as a wall
+    pub scale: (f64, f64),
+    pub anchor: Anchor,
+    /// Extra height in world units added on top of the anchor, such as an item resting on a table
+    pub z_offset: f64
 }
 
 impl Sprite{
+    /// Creates a sprite that looks the same from every side
+    pub fn new(pos: (f64, f64), texture_index: u8) -> Self{
+        Sprite{
+            pos,
+            texture_index,
+            dist: 0.0,
+            facing: 0.0,
+            def: None,
+            animation: None,
+            scale: (1.0, 1.0),
+            anchor: Anchor::Floor,
+            z_offset: 0.0
+        }
+    }
+
+    /// Creates a sprite facing the given angle that shows a different atlas entry depending on where it is seen from
+    pub fn directional(pos: (f64, f64), facing: f64, def: SpriteDef) -> Self{
+        let texture_index = def.rotations[0].texture_index;
+        Sprite{
+            facing,
+            def: Some(def),
+            ..Sprite::new(pos, texture_index)
+        }
+    }
+
+    /// Starts playing a clip from its first frame, replacing whatever was playing
+    pub fn play(&mut self, clip: Clip){
+        self.animation = Some(Animation::new(clip));
+    }
+
+    /// Moves the sprite's animation on
+    pub fn update(&mut self, dt: f64){
+        if let Some(animation) = self.animation.as_mut() {
+            animation.update(dt);
+        }
+    }
+
+    /// Gets the height of the bottom of the sprite above the floor
+    pub fn base_height(&self) -> f64{
+        let height = self.scale.1;
+        let base = match self.anchor{
+            Anchor::Floor => 0.0,
+            Anchor::Centre => (1.0 - height) / 2.0,
+            Anchor::Ceiling => 1.0 - height
+        };
+        base + self.z_offset
+    }
+
     pub fn eucl_dist(&mut self, pos2: (f64, f64)) -> f64{
         // c^2 = a^2 + b^2
         let a = pos2.0 - self.pos.0;
         let b = pos2.1 - self.pos.1;
-        return (a*a + b*b).sqrt();
+        (a*a + b*b).sqrt()
     }
-}
\ No newline at end of file
+
+    /// Gets how to draw the sprite when it is seen from the given position, picking the rotation closest to the angle
+    /// between the sprite's facing and the viewer. A playing clip's frame takes the place of the definition
+    pub fn rotation_for(&self, view_pos: (f64, f64)) -> Rotation{
+        let view_angle = (view_pos.1 - self.pos.1).atan2(view_pos.0 - self.pos.0) - self.facing;
+        if let Some(animation) = &self.animation {
+            let textures = &animation.frame().textures;
+            return Rotation::new(textures[rotation_index(textures.len(), view_angle)], false);
+        }
+        match &self.def{
+            Some(def) => def.rotation_at(view_angle),
+            None => Rotation::new(self.texture_index, false)
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use image::{Rgba, RgbaImage};
+
+use crate::palette::Palette;
+
+/// How textures are sampled when they are drawn
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Filter{
+    /// The closest pixel of the full size texture, gives the blocky retro look
+    Nearest,
+    /// A blend of the four closest pixels from the mipmap that best matches the size on screen
+    Bilinear,
+    /// Bilinear samples from the two closest mipmaps blended together
+    Trilinear
+}
+
+/// One level of a mip chain, stored column by column as walls and sprites are drawn in vertical strips
+#[derive(Clone)]
+struct MipLevel{
+    width: u32,
+    height: u32,
+    pixels: Vec<Rgba<u8>>
+}
+
+impl MipLevel{
+    fn get(&self, x: u32, y: u32) -> Rgba<u8>{
+        self.pixels[(x.min(self.width - 1) * self.height + y.min(self.height - 1)) as usize]
+    }
+
+    /// Halves the level in each direction by averaging blocks of 2x2 pixels
+    fn downsample(&self) -> MipLevel{
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for x in 0..width{
+            for y in 0..height{
+                let mut sum = [0u32; 4];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)]{
+                    let pixel = self.get(x * 2 + dx, y * 2 + dy);
+                    for i in 0..4{
+                        sum[i] += pixel[i] as u32;
+                    }
+                }
+                pixels.push(Rgba([(sum[0] / 4) as u8, (sum[1] / 4) as u8, (sum[2] / 4) as u8, (sum[3] / 4) as u8]));
+            }
+        }
+        MipLevel{ width, height, pixels }
+    }
+
+    /// Blends the four pixels around a texture co-ordinate. Wrapping around the edges keeps tiled textures seamless,
+    /// otherwise the edge pixels are repeated so sprites don't pick up colour from their opposite side
+    fn bilinear(&self, u: f64, v: f64, wrap: bool) -> Rgba<u8>{
+        let x = u * self.width as f64 - 0.5;
+        let y = v * self.height as f64 - 0.5;
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let (w, h) = (self.width as i64, self.height as i64);
+        let (x0, y0) = (x.floor() as i64, y.floor() as i64);
+        let (x0, x1, y0, y1) = if wrap {
+            (x0.rem_euclid(w), (x0 + 1).rem_euclid(w), y0.rem_euclid(h), (y0 + 1).rem_euclid(h))
+        }
+        else {
+            (x0.clamp(0, w - 1), (x0 + 1).clamp(0, w - 1), y0.clamp(0, h - 1), (y0 + 1).clamp(0, h - 1))
+        };
+        let (x0, x1, y0, y1) = (x0 as u32, x1 as u32, y0 as u32, y1 as u32);
+
+        let (a, b, c, d) = (self.get(x0, y0), self.get(x1, y0), self.get(x0, y1), self.get(x1, y1));
+        let mut pixel = Rgba([0, 0, 0, 0]);
+        for i in 0..4{
+            let top = a[i] as f64 * (1.0 - fx) + b[i] as f64 * fx;
+            let bottom = c[i] as f64 * (1.0 - fx) + d[i] as f64 * fx;
+            pixel[i] = (top * (1.0 - fy) + bottom * fy).round() as u8;
+        }
+        pixel
+    }
+}
+
+/// A heap backed texture of any size along with its mip chain, which is built when the texture is loaded
+#[derive(Clone)]
+pub struct Texture{
+    pub width: u32,
+    pub height: u32,
+    levels: Vec<MipLevel>
+}
+
+impl Texture{
+    /// Loads an image file into a texture
+    pub fn load(path: &str) -> Self{
+        Texture::from_image(&image::open(path).unwrap().to_rgba8())
+    }
+
+    /// Converts an image into a texture, halving it down to a single pixel to build the mip chain
+    pub fn from_image(img: &RgbaImage) -> Self{
+        let (width, height) = img.dimensions();
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for x in 0..width{
+            for y in 0..height{
+                pixels.push(*img.get_pixel(x, y));
+            }
+        }
+        let mut levels = vec![MipLevel{ width, height, pixels }];
+        while let Some(last) = levels.last() {
+            if last.width == 1 && last.height == 1 { break; }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        Texture{ width, height, levels }
+    }
+
+    /// Replaces every pixel of the texture and its mipmaps with the closest colour in a palette
+    pub fn quantize(&mut self, palette: &Palette){
+        for level in self.levels.iter_mut(){
+            for pixel in level.pixels.iter_mut(){
+                *pixel = palette.quantize(*pixel);
+            }
+        }
+    }
+
+    /// Gets the pixel at the given position on the texture, positions past the edge are clamped to it
+    pub fn get(&self, x: u32, y: u32) -> Rgba<u8>{
+        self.levels[0].get(x, y)
+    }
+
+    /// Gets the nearest pixel for a texture co-ordinate where 0.0 to 1.0 spans the whole texture
+    pub fn sample(&self, u: f64, v: f64) -> Rgba<u8>{
+        self.get((u * self.width as f64) as u32, (v * self.height as f64) as u32)
+    }
+
+    /// Samples a texture co-ordinate with the given filter. `footprint` is how many pixels of the full size texture
+    /// one pixel on screen covers, and picks which mipmap to read from. `wrap` should be set for textures that tile
+    pub fn sample_filtered(&self, u: f64, v: f64, footprint: f64, filter: Filter, wrap: bool) -> Rgba<u8>{
+        let lod = footprint.max(1.0).log2().min((self.levels.len() - 1) as f64);
+        match filter{
+            Filter::Nearest => self.sample(u, v),
+            Filter::Bilinear => self.levels[lod.round() as usize].bilinear(u, v, wrap),
+            Filter::Trilinear => {
+                let level = lod.floor() as usize;
+                let near = self.levels[level].bilinear(u, v, wrap);
+                if level + 1 >= self.levels.len() { return near; }
+                let far = self.levels[level + 1].bilinear(u, v, wrap);
+                let t = lod - level as f64;
+                let mut pixel = near;
+                for i in 0..4{
+                    pixel[i] = (near[i] as f64 * (1.0 - t) + far[i] as f64 * t).round() as u8;
+                }
+                pixel
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use raycast_rust::animation::{Animation, Clip, Playback};
+use raycast_rust::sprite::{Rotation, Sprite};
+
+#[test]
+fn empty_clips_are_rejected(){
+    assert!(Clip::uniform(&[], 0.1, Playback::Loop).is_none());
+    assert!(Clip::new(Vec::new(), Playback::Once).is_none());
+    assert!(Clip::uniform(&[1, 2], 0.1, Playback::Loop).is_some());
+}
+
+/// One-shot clips hold their last frame once they finish, and sprites show it in place of their own texture
+#[test]
+fn finished_clips_hold_their_last_frame(){
+    let clip = Clip::uniform(&[4, 5], 0.1, Playback::Once).unwrap();
+    assert_eq!(clip.frames().len(), 2);
+    assert!(clip.playback() == Playback::Once);
+
+    let mut animation = Animation::new(clip.clone());
+    animation.update(0.5);
+    assert!(animation.is_finished());
+    assert_eq!(animation.frame().textures, vec![5]);
+
+    let mut sprite = Sprite::new((1.5, 1.5), 3);
+    sprite.play(clip);
+    sprite.update(0.5);
+    assert_eq!(sprite.rotation_for((0.5, 0.5)), Rotation::new(5, false));
+}
@@ -0,0 +1,63 @@
+//! A small scene shared by the rendering tests, textured with solid colours so pixels can be checked exactly
+// Each test file only uses part of the scene
+#![allow(dead_code)]
+
+use image::{Rgba, RgbaImage};
+use raycast_rust::{input_handler, map, player, renderer, settings, sky, sprite, texture};
+
+pub const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+pub const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+pub const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+/// Creates a texture filled with a single colour
+pub fn solid(colour: Rgba<u8>) -> texture::Texture{
+    texture::Texture::from_image(&RgbaImage::from_pixel(4, 4, colour))
+}
+
+/// A renderer drawing red walls, a green floor and a blue ceiling at the given resolution, with one ray per column
+pub fn renderer(width: u32, height: u32) -> renderer::Renderer{
+    renderer::Renderer::new(
+        vec![solid(RED), solid(GREEN), solid(BLUE)],
+        vec![solid(RED)],
+        sky::Sky::new(solid(BLUE)),
+        settings::Settings{
+            resolution: (width, height),
+            ray_count: width as usize,
+            ..Default::default()
+        }
+    )
+}
+
+/// A room walled in around its edge with a pillar in one corner, floored and ceilinged throughout
+pub fn world() -> map::Map{
+    map::Map::new([
+        [1,1,1,1,1,1,1,1,1,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,1,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,0,0,0,0,0,0,0,0,1],
+        [1,1,1,1,1,1,1,1,1,1]
+    ], 2, 3)
+}
+
+/// A player in the middle of the room looking towards the wall at x = 0
+pub fn player() -> player::Player{
+    player::Player{
+        plane: (0.0, 0.66),
+        dir: (-1.0, 0.0),
+        pos: (5.5, 5.5),
+        pos_z: player::EYE_HEIGHT,
+        vel_z: 0.0,
+        pitch: 0.0,
+        ih: input_handler::InputHandler::new()
+    }
+}
+
+/// A sprite standing between the player and the wall they are looking at
+pub fn sprites() -> Vec<sprite::Sprite>{
+    vec![sprite::Sprite::new((3.5, 4.5), 0)]
+}
@@ -0,0 +1,58 @@
+mod common;
+
+/// The ray through the middle of the screen travels straight down the room to the wall at x = 0,
+/// whose face is 4.5 units in front of the player
+#[test]
+fn centre_column_hits_the_wall_ahead(){
+    let renderer = common::renderer(32, 24);
+    let (frame, depth) = renderer.render(&common::player(), &common::world(), &mut Vec::new());
+
+    assert_eq!((frame.width(), frame.height()), (32, 24));
+    assert_eq!((depth.width, depth.height), (32, 24));
+    assert!((depth.get(16, 12) - 4.5).abs() < 1e-9);
+
+    // The wall is red, darkened by the shadow on its side and the fog but never tinted another colour
+    let pixel = frame.get_pixel(16, 12);
+    assert_eq!(pixel[3], 255);
+    assert!(pixel[0] > 0);
+    assert_eq!((pixel[1], pixel[2]), (0, 0));
+}
+
+#[test]
+fn floor_and_ceiling_are_drawn_in_front_of_the_wall(){
+    let renderer = common::renderer(32, 24);
+    let (frame, depth) = renderer.render(&common::player(), &common::world(), &mut Vec::new());
+
+    // The bottom row is floor right in front of the player and the top row is ceiling
+    let (floor, ceiling) = (frame.get_pixel(16, 23), frame.get_pixel(16, 0));
+    assert_eq!(floor[3], 255);
+    assert_eq!(ceiling[3], 255);
+    assert!(floor[1] > 0 && floor[0] == 0 && floor[2] == 0);
+    assert!(ceiling[2] > 0 && ceiling[0] == 0 && ceiling[1] == 0);
+    assert!(depth.get(16, 23) < depth.get(16, 12));
+    assert!(depth.get(16, 0) < depth.get(16, 12));
+}
+
+#[test]
+fn sprites_write_their_depth(){
+    let renderer = common::renderer(32, 24);
+    let play = common::player();
+    let mut sprites = common::sprites();
+    let (_, depth) = renderer.render(&play, &common::world(), &mut sprites);
+
+    // The sprite is 2 units in front of the player and 1 to their left, which is where the camera plane puts it on screen
+    let x = (16.0 * (1.0 - 1.0 / 0.66 / 2.0)) as u32;
+    assert!((depth.get(x, 12) - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn sprites_filling_the_screen_cover_every_edge(){
+    let renderer = common::renderer(32, 24);
+    // Half a unit away the sprite is twice the height of the frame and wider than it
+    let mut sprites = vec![raycast_rust::sprite::Sprite::new((5.0, 5.5), 0)];
+    let (_, depth) = renderer.render(&common::player(), &common::world(), &mut sprites);
+
+    for (x, y) in [(0, 0), (31, 0), (0, 23), (31, 23), (16, 23)]{
+        assert!((depth.get(x, y) - 0.5).abs() < 1e-9, "sprite missing at {}, {}", x, y);
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use raycast_rust::palette::Palette;
+
+/// The fog colormaps follow the map's fog so coloured fog is a lookup like black fog
+#[test]
+fn fog_colormaps_follow_the_map_fog(){
+    let mut renderer = common::renderer(32, 24);
+    let mut world = common::world();
+    world.fog.colour = [0, 255, 0];
+    let palette = Palette::new(&[[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]]);
+    renderer.use_palette(palette, &world.fog);
+    assert_eq!(renderer.palette().unwrap().fog_colour(), [0, 255, 0]);
+
+    world.fog.colour = [0, 0, 255];
+    renderer.update_fog(&world.fog);
+    assert_eq!(renderer.palette().unwrap().fog_colour(), [0, 0, 255]);
+
+    // Fully fogged pixels come out as the fog colour
+    world.fog.end = 1.0;
+    let (frame, _) = renderer.render(&common::player(), &world, &mut Vec::new());
+    assert_eq!(*frame.get_pixel(16, 12), common::BLUE);
+}
+
+/// Clearing the palette puts the truecolor textures back, so the frame matches one that never used a palette
+#[test]
+fn clearing_the_palette_restores_truecolor(){
+    let world = common::world();
+    let (truecolour, _) = common::renderer(32, 24).render(&common::player(), &world, &mut Vec::new());
+
+    let mut renderer = common::renderer(32, 24);
+    renderer.use_palette(Palette::new(&[[0, 0, 0], [128, 128, 128]]), &world.fog);
+    let (indexed, _) = renderer.render(&common::player(), &world, &mut Vec::new());
+    assert!(indexed != truecolour);
+
+    renderer.clear_palette();
+    assert!(renderer.palette().is_none());
+    let (frame, _) = renderer.render(&common::player(), &world, &mut Vec::new());
+    assert!(frame == truecolour);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use raycast_rust::{map, player};
+
+/// Renders the same view serially and across all cores and checks the frames and depths are exactly the same
+fn assert_parallel_matches_serial(play: &player::Player, world: &map::Map){
+    let mut serial = common::renderer(64, 48);
+    serial.settings.parallel = false;
+    let mut parallel = common::renderer(64, 48);
+    parallel.settings.parallel = true;
+
+    let (serial_frame, serial_depth) = serial.render(play, world, &mut common::sprites());
+    let (parallel_frame, parallel_depth) = parallel.render(play, world, &mut common::sprites());
+
+    assert!(serial_frame == parallel_frame);
+    for x in 0..64{
+        for y in 0..48{
+            assert_eq!(serial_depth.get(x, y).to_bits(), parallel_depth.get(x, y).to_bits(), "depth differs at {}, {}", x, y);
+        }
+    }
+}
+
+/// The room with a wall lower than the eye right in front of the player
+fn low_wall_world() -> map::Map{
+    let mut world = common::world();
+    world.set(map::Layer::Wall, 4, 5, 1);
+    world.set_height(4, 5, 0.4);
+    world
+}
+
+/// Casting and drawing across all cores must give exactly the same frame and depth as doing it on one thread
+#[test]
+fn parallel_matches_serial(){
+    let mut play = common::player();
+    // Look across the room at an angle so the columns hit different walls at different distances
+    play.dir = (-0.8, 0.6);
+    play.plane = (0.396, 0.528);
+    assert_parallel_matches_serial(&play, &common::world());
+}
+
+/// A low wall close to the camera has its top edge, and the far edge of its top face, below the bottom of the frame
+#[test]
+fn parallel_matches_serial_next_to_a_low_wall(){
+    let mut play = common::player();
+    play.pos = (5.05, 5.5);
+    assert_parallel_matches_serial(&play, &low_wall_world());
+}
+
+/// Looking fully up or down from high above the floor moves the horizon, and every wall edge with it, off the frame
+#[test]
+fn parallel_matches_serial_looking_up_and_down_while_jumping(){
+    let world = low_wall_world();
+    for pitch in [-0.5, 0.5]{
+        for pos_z in [player::EYE_HEIGHT, 0.95]{
+            let mut play = common::player();
+            play.pos = (5.9, 5.5);
+            play.pitch = pitch;
+            play.pos_z = pos_z;
+            assert_parallel_matches_serial(&play, &world);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use raycast_rust::renderer::Renderer;
+use raycast_rust::segment::Segment;
+
+#[test]
+fn segments_touch_every_cell_they_cross(){
+    let segment = Segment::new((5.5, 3.5), (7.5, 3.2), 1);
+    assert!(segment.touches_cell((5, 3)));
+    assert!(segment.touches_cell((6, 3)));
+    assert!(segment.touches_cell((7, 3)));
+    assert!(!segment.touches_cell((4, 3)));
+    assert!(!segment.touches_cell((6, 2)));
+    assert!(!segment.touches_cell((8, 3)));
+}
+
+/// A ray that crosses a long segment away from the cell holding its midpoint still hits it, and only once
+#[test]
+fn rays_hit_segments_outside_their_middle_cell(){
+    let mut world = common::world();
+    world.segments.push(Segment::new((5.5, 3.5), (7.5, 3.5), 2));
+    let mut play = common::player();
+    play.pos = (7.2, 6.5);
+    play.dir = (0.0, -1.0);
+    play.plane = (0.66, 0.0);
+
+    let ray = Renderer::cast_ray(&play, &world, 0.0, world.max_height());
+    let hits: Vec<_> = ray.hits.iter().filter(|hit| hit.texture_index == 2).collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].cell, (7, 3));
+    assert!((hits[0].length - 3.0).abs() < 1e-9);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use image::{Rgba, RgbaImage};
+use raycast_rust::{map, mirror, portal, player, renderer, sky, texture};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// A renderer whose panorama is a different colour in each quarter, red, green, blue then white
+fn quartered_sky() -> renderer::Renderer{
+    let mut renderer = common::renderer(64, 48);
+    let quarters = [common::RED, common::GREEN, common::BLUE, WHITE];
+    renderer.sky = sky::Sky::new(texture::Texture::from_image(&RgbaImage::from_fn(4, 1, |x, _| quarters[x as usize])));
+    renderer
+}
+
+/// The room under open sky with every wall low enough to see over, apart from one full height wall at (0, 8)
+fn open_world() -> map::Map{
+    let mut world = common::world();
+    for x in 0..10{
+        for y in 0..10{
+            world.set(map::Layer::Ceiling, x, y, 0);
+            if world.get(map::Layer::Wall, x, y) > 0 { world.set_height(x, y, 0.2); }
+        }
+    }
+    world.set_height(0, 8, 1.0);
+    world
+}
+
+/// A player looking along (-0.8, 0.6), which shows the third quarter of the sky. The centre ray meets
+/// the x face of the wall at (0, 8) 5.625 units away
+fn player() -> player::Player{
+    let mut play = common::player();
+    play.dir = (-0.8, 0.6);
+    play.plane = (0.396, 0.528);
+    play
+}
+
+/// A mirror under open sky shows the sky the ray bounces towards, (0.8, 0.6), which is in the last quarter
+#[test]
+fn mirrors_reflect_the_sky(){
+    let mut world = open_world();
+    world.mirrors.push(mirror::Mirror::new((0, 8), [255, 255, 255], 1.0));
+    let (frame, _) = quartered_sky().render(&player(), &world, &mut Vec::new());
+
+    // Just above the horizon, inside the mirror
+    assert_eq!(*frame.get_pixel(32, 22), WHITE);
+}
+
+/// A portal turned a quarter turn shows the sky the ray comes out towards, (-0.6, -0.8), which is in the second quarter
+#[test]
+fn portals_turn_the_sky(){
+    let mut world = open_world();
+    world.link_portals((0, 8), portal::Face::MaxX, (5, 9), portal::Face::MinY);
+    let (frame, _) = quartered_sky().render(&player(), &world, &mut Vec::new());
+
+    assert_eq!(*frame.get_pixel(32, 22), common::GREEN);
+}
@@ -0,0 +1,32 @@
+use std::f64::consts::FRAC_PI_2;
+
+use raycast_rust::sprite::{Rotation, Sprite, SpriteDef};
+
+#[test]
+fn sprite_defs_need_a_supported_number_of_rotations(){
+    assert!(SpriteDef::from_textures(&[]).is_none());
+    assert!(SpriteDef::from_textures(&[1, 2, 3]).is_none());
+    assert!(SpriteDef::from_textures(&[1]).is_some());
+    assert!(SpriteDef::from_textures(&[0; 8]).is_some());
+    assert!(SpriteDef::from_textures(&[0; 16]).is_some());
+}
+
+/// The 3 views on the far side of a symmetric sprite reuse the near side's entries flipped
+#[test]
+fn symmetric_defs_mirror_one_side(){
+    let def = SpriteDef::symmetric([10, 11, 12, 13, 14]);
+    let entries: Vec<(u8, bool)> = def.rotations().iter().map(|rotation| (rotation.texture_index, rotation.mirrored)).collect();
+    assert_eq!(entries, vec![(10, false), (11, false), (12, false), (13, false), (14, false), (13, true), (12, true), (11, true)]);
+    assert!((def.angle(2) - FRAC_PI_2).abs() < 1e-12);
+}
+
+#[test]
+fn directional_sprites_show_the_rotation_facing_the_viewer(){
+    let sprite = Sprite::directional((5.0, 5.0), 0.0, SpriteDef::symmetric([10, 11, 12, 13, 14]));
+    assert_eq!(sprite.texture_index, 10);
+    // In front, a quarter turn round, behind and a quarter turn the other way
+    assert_eq!(sprite.rotation_for((6.0, 5.0)), Rotation::new(10, false));
+    assert_eq!(sprite.rotation_for((5.0, 6.0)), Rotation::new(12, false));
+    assert_eq!(sprite.rotation_for((4.0, 5.0)), Rotation::new(14, false));
+    assert_eq!(sprite.rotation_for((5.0, 4.0)), Rotation::new(12, true));
+}